@@ -1,6 +1,7 @@
 use iced::widget::{Column, column, container, horizontal_space, row, scrollable, text};
 use iced::{Element, Fill, Length, color};
-use nadi_core::attrs::AttrMap;
+use nadi_core::attrs::{AttrMap, HasAttributes};
+use nadi_core::tasks::TaskContext;
 
 #[derive(Default)]
 pub struct AttrView {
@@ -13,16 +14,16 @@ impl AttrView {
     pub fn load_attrs(&mut self, label: String, attrs: &AttrMap) {
         // self.attributes = attrs.clone()
         self.label = label;
-        self.values = attrs
-            .iter()
-            .map(|item| {
-                (
-                    item.0.to_string(),
-                    item.1.type_name().to_string(),
-                    item.1.to_string(),
-                )
-            })
-            .collect();
+        self.values = attr_values("", attrs);
+    }
+
+    /// Load both the env and the network attributes, named the same
+    /// way they are in the tasks
+    pub fn load_context(&mut self, label: String, ctx: &TaskContext) {
+        self.label = label;
+        self.values = attr_values("env.", &ctx.env);
+        self.values
+            .extend(attr_values("network.", ctx.network.attr_map()));
     }
 
     pub fn view(&self) -> Element<'_, ()> {
@@ -63,6 +64,19 @@ impl AttrView {
     }
 }
 
+fn attr_values(prefix: &str, attrs: &AttrMap) -> Vec<(String, String, String)> {
+    attrs
+        .iter()
+        .map(|item| {
+            (
+                format!("{prefix}{}", item.0),
+                item.1.type_name().to_string(),
+                item.1.to_string(),
+            )
+        })
+        .collect()
+}
+
 fn tab_title(theme: &iced::Theme) -> container::Style {
     let mut style = container::Style::default();
     style.background = Some(iced::Background::Color(
@@ -0,0 +1,86 @@
+use colored::Colorize;
+use nadi::editor::format::format;
+use nadi::editor::my_hl::NadiFileType;
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+static USAGE: &str = "Usage:
+  nadi-fmt <files>...                 Print the formatted files
+  nadi-fmt --write <files>...         Format the files in place
+  nadi-fmt --check <files>...         Exit with error if any file is not formatted
+  nadi-fmt --stdin <type>             Format the text from stdin
+
+<type> can be one of: tasks, network";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !std::io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+    match cli(&args) {
+        Ok(true) => (),
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("{}: {e}\n\n{USAGE}", "Error".red());
+            std::process::exit(1);
+        }
+    }
+}
+
+fn file_type(path: &Path) -> Result<NadiFileType, String> {
+    path.extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .and_then(|e| NadiFileType::from_str(e).ok())
+        .ok_or(format!("Unknown file type: {}", path.display()))
+}
+
+/// Returns false if some of the files are not formatted with --check
+fn cli(args: &[String]) -> Result<bool, String> {
+    let (mode, files) = match args.first().map(String::as_str) {
+        None => return Err("No files given".into()),
+        Some("-h" | "--help") => {
+            println!("{USAGE}");
+            return Ok(true);
+        }
+        Some("--stdin") => {
+            let nft = match args.get(1) {
+                Some(t) => NadiFileType::from_str(t).map_err(|_| format!("Unknown type: {t}"))?,
+                None => return Err("--stdin needs the file type".into()),
+            };
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|e| e.to_string())?;
+            print!("{}", format(&text, &nft)?);
+            return Ok(true);
+        }
+        Some(m @ ("--write" | "--check")) => (m, &args[1..]),
+        Some(_) => ("--print", args),
+    };
+    if files.is_empty() {
+        return Err("No files given".into());
+    }
+    let mut ok = true;
+    for file in files {
+        let path = Path::new(file);
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{file}: {e}"))?;
+        let formatted = format(&text, &file_type(path)?).map_err(|e| format!("{file}: {e}"))?;
+        match mode {
+            "--write" => {
+                if formatted != text {
+                    std::fs::write(path, formatted).map_err(|e| format!("{file}: {e}"))?;
+                    println!("{} {file}", "Formatted".green());
+                }
+            }
+            "--check" => {
+                if formatted != text {
+                    ok = false;
+                    println!("{} {file}", "Unformatted".red());
+                }
+            }
+            _ => print!("{formatted}"),
+        }
+    }
+    Ok(ok)
+}
@@ -1,9 +1,115 @@
-use nadi::help::MdHelp;
+use colored::Colorize;
+use nadi::help::examples::{Sandbox, extract_examples};
+use nadi::help::{self, FuncType, MdHelp};
 use nadi::icons;
+use nadi_core::functions::NadiFunctions;
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+static USAGE: &str = "Usage:
+  nadi-help                                 Open the help browser
+  nadi-help --print <type> <function>       Print help for the function
+  nadi-help --list [--type <type>] [query]  List (and filter) the functions
+  nadi-help --check-examples [--type <type>] [query]
+                                            Run the examples in the functions help
+
+<type> can be one of: env, node, network";
 
 fn main() -> iced::Result {
-    iced::application("NADI Help", MdHelp::update, MdHelp::view)
-        .font(icons::FONT)
-        .theme(MdHelp::theme)
-        .run()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        return iced::application("NADI Help", MdHelp::update, MdHelp::view)
+            .font(icons::FONT)
+            .theme(MdHelp::theme)
+            .subscription(MdHelp::subscription)
+            .run();
+    }
+    if !std::io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+    if let Err(e) = cli(&args) {
+        eprintln!("{}: {e}\n\n{USAGE}", "Error".red());
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn cli(args: &[String]) -> Result<(), String> {
+    match args[0].as_str() {
+        "-h" | "--help" => println!("{USAGE}"),
+        "--print" => {
+            let (ty, name) = match &args[1..] {
+                [ty, name] => (FuncType::from_str(ty)?, name),
+                _ => return Err("--print needs function type and name".into()),
+            };
+            let functions = NadiFunctions::new();
+            let md = help::function_help(&functions, &ty, name)
+                .ok_or(format!("{ty} function {name} not found"))?;
+            println!("{}", help::ansi::markdown_to_ansi(&md));
+        }
+        "--list" => {
+            let (state, query) = filter_args(&args[1..])?;
+            let functions = NadiFunctions::new();
+            let funcs = help::list_functions(&functions, &state, &query);
+            let width = funcs.iter().map(|f| f.1.len()).max().unwrap_or_default();
+            for (ty, name) in funcs {
+                let short = help::function_short_help(&functions, &ty, name).unwrap_or_default();
+                println!(
+                    "{:<7} {} {}",
+                    ty.to_string().red(),
+                    format!("{name:<width$}").blue(),
+                    short
+                );
+            }
+        }
+        "--check-examples" => {
+            let (state, query) = filter_args(&args[1..])?;
+            let functions = NadiFunctions::new();
+            let mut sandbox = Sandbox::new(help::plugins::copy_functions(&functions));
+            let (mut total, mut failed) = (0, 0);
+            for (ty, name) in help::list_functions(&functions, &state, &query) {
+                let Some(page) = help::function_page(&functions, &ty, name) else {
+                    continue;
+                };
+                for (i, (_, code)) in extract_examples(&page.long).iter().enumerate() {
+                    total += 1;
+                    let res = sandbox
+                        .run(code)
+                        .unwrap_or_else(|| Err("Could not capture the stdout".to_string()));
+                    match res {
+                        Ok(_) => println!("{} {ty} {name} #{}", "OK".green(), i + 1),
+                        Err(e) => {
+                            failed += 1;
+                            println!("{} {ty} {name} #{}", "FAIL".red(), i + 1);
+                            for line in e.trim_end().lines() {
+                                println!("    {line}");
+                            }
+                        }
+                    }
+                }
+            }
+            println!("\n{total} examples, {failed} failed");
+            if failed > 0 {
+                std::process::exit(1);
+            }
+        }
+        a => return Err(format!("Invalid argument {a}")),
+    }
+    Ok(())
+}
+
+/// Function type and search query from `[--type <type>] [query]`
+fn filter_args(args: &[String]) -> Result<(Option<FuncType>, String), String> {
+    let mut state = None;
+    let mut query = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--type" {
+            let ty = args.next().ok_or("--type needs a function type")?;
+            state = Some(FuncType::from_str(ty)?);
+        } else {
+            query.push(arg.as_str());
+        }
+    }
+    Ok((state, query.join(" ")))
 }
@@ -1,27 +1,124 @@
 use crate::help::FuncType;
 use crate::icons;
 use iced::highlighter;
-use iced::widget::{column, horizontal_space, pick_list, row, text, text_editor, vertical_rule};
-use iced::{Element, Fill, Font, Task, Theme};
+use iced::keyboard::{Key, key::Named};
+use iced::widget::{
+    Column, button, column, container, horizontal_space, pick_list, responsive, rich_text, row,
+    scrollable, span, stack, text, text_editor, text_input, toggler, tooltip, vertical_rule,
+    vertical_space,
+};
+use iced::{Color, Element, Fill, Font, Length, Padding, Pixels, Subscription, Task, Theme};
 use nadi_core::{
     parser::tasks,
     parser::tokenizer::{self, TaskToken},
     tasks::{TaskInput, TaskKeyword, TaskType},
 };
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+pub mod brackets;
+pub mod cells;
+pub mod complete;
+pub mod diagnostics;
+pub mod diff;
+pub mod find;
+pub mod format;
+pub mod history;
+pub mod lines;
+pub mod lint;
 pub mod my_hl;
+pub mod outline;
+pub mod palette;
+pub mod template;
+
+use diagnostics::{Diagnostic, Severity};
+
+// sizes used to line up the gutter with the editor lines
+static TEXT_SIZE: f32 = 16.0;
+static LINE_HEIGHT: f32 = 20.0;
+static CHAR_WIDTH: f32 = 10.0;
+static EDITOR_PADDING: f32 = 5.0;
+static EXEC_WIDTH: f32 = 4.0;
+static MARKER_WIDTH: f32 = 20.0;
+
+/// Pause in the typing before the buffer is parsed for diagnostics
+static DIAGNOSTICS_DELAY: Duration = Duration::from_millis(300);
 
 pub struct Editor {
     theme: highlighter::Theme,
     pub function: Option<(FuncType, String)>,
-    signature: String,
+    /// index of the argument of the function under the cursor
+    active_arg: Option<usize>,
     file: Option<PathBuf>,
     is_dirty: bool,
+    /// buffer when it was last loaded or saved, undoing back to it
+    /// makes the buffer clean again
+    saved_text: String,
     is_loading: bool,
     pub content: text_editor::Content,
     embedded: bool,
+    /// names for the completion, kept updated by the main window
+    pub symbols: Arc<complete::Symbols>,
+    completions: Vec<complete::Completion>,
+    completion_sel: usize,
+    /// number of chars before the cursor the completion replaces
+    completion_len: usize,
+    diagnostics: Vec<Diagnostic>,
+    /// when to check the diagnostics after the last edit
+    diagnostics_due: Option<Instant>,
+    /// vertical scroll of the editor in pixels, followed from the
+    /// actions so the gutter lines up with the editor lines
+    scroll: f32,
+    /// cursor position the scroll was last followed at
+    scroll_cursor: (usize, usize),
+    /// height of the text in the editor from the last view
+    text_height: Cell<f32>,
+    /// find and replace bar, None when it is closed
+    find: Option<find::Find>,
+    find_id: text_input::Id,
+    /// cursor position (column in bytes) the selection started from,
+    /// the cursor is at its other end
+    select_anchor: (usize, usize),
+    history: history::History,
+    /// lines run in the terminal during this session
+    executed: BTreeSet<usize>,
+    /// modification time of the file when it was loaded or saved
+    modified: Option<SystemTime>,
+    /// modification time of the file seen by the last check, the
+    /// buffer is only in sync with it after reloading or resolving
+    /// the conflict
+    polled: Option<SystemTime>,
+    conflict: Option<Conflict>,
+    /// error from reading the file after it changed on disk
+    file_error: Option<String>,
+    /// tasks and sections of the last parsed tasks file
+    outline: Vec<outline::OutlineItem>,
+    /// line ranges of the `# %%` cells
+    cells: Vec<Range<usize>>,
+    /// lines the debugger pauses at
+    breakpoints: BTreeSet<usize>,
+    /// line of the task the debugger is paused at
+    debug_line: Option<usize>,
+    /// edits are ignored while the debugger runs the tasks from the
+    /// lines
+    read_only: bool,
+    /// brackets in the buffer, for matching the one at the cursor
+    brackets: Vec<brackets::Bracket>,
+    /// color the brackets by their depth
+    rainbow: bool,
+}
+
+/// File on disk that changed while the buffer has unsaved changes
+struct Conflict {
+    disk: String,
+    /// changes from the buffer to the file, when shown
+    diff: Option<Vec<diff::DiffLine>>,
+    /// found when saving, overwriting the file needs confirmation
+    saving: bool,
 }
 
 impl Default for Editor {
@@ -29,12 +126,38 @@ impl Default for Editor {
         Self {
             theme: highlighter::Theme::SolarizedDark,
             function: None,
-            signature: String::new(),
+            active_arg: None,
             file: None,
             is_dirty: false,
+            saved_text: String::new(),
             is_loading: false,
             content: text_editor::Content::default(),
             embedded: false,
+            symbols: Arc::default(),
+            completions: vec![],
+            completion_sel: 0,
+            completion_len: 0,
+            diagnostics: vec![],
+            diagnostics_due: None,
+            scroll: 0.0,
+            scroll_cursor: (0, 0),
+            text_height: Cell::new(f32::INFINITY),
+            find: None,
+            find_id: text_input::Id::unique(),
+            select_anchor: (0, 0),
+            history: history::History::default(),
+            executed: BTreeSet::new(),
+            modified: None,
+            polled: None,
+            conflict: None,
+            file_error: None,
+            outline: vec![],
+            cells: cells::cells(""),
+            breakpoints: BTreeSet::new(),
+            debug_line: None,
+            read_only: false,
+            brackets: vec![],
+            rainbow: false,
         }
     }
 }
@@ -43,6 +166,7 @@ impl Default for Editor {
 pub enum Message {
     EditorAction(text_editor::Action),
     ThemeChange(highlighter::Theme),
+    ToggleRainbow(bool),
     NewFile,
     OpenFile,
     FileOpened(Result<(PathBuf, Arc<String>), Error>),
@@ -50,14 +174,73 @@ pub enum Message {
     FileSaved(Result<PathBuf, Error>),
     Comment,
     FuncAtMark(Option<(FuncType, String)>),
+    // sent from the main window, text to insert at the cursor, and the
+    // range (in chars) within it to select afterwards
+    InsertText(String, Option<Range<usize>>),
+    Complete,
+    CompletionMove(bool),
+    AcceptCompletion(usize),
+    CloseCompletion,
+    CheckDiagnostics,
+    Diagnostics(diagnostics::Report),
+    GotoPosition(usize, usize),
+    OpenFind,
+    CloseFind,
+    FindChange(String),
+    ReplaceChange(String),
+    FindToggle(find::FindOption),
+    FindNext,
+    FindPrevious,
+    ReplaceOne,
+    ReplaceAll,
+    Undo,
+    Redo,
+    Lines(lines::LineCommand),
+    Format,
+    CheckFile,
+    FileChanged(Result<(PathBuf, Arc<String>), Error>),
+    ReloadFile,
+    KeepMine,
+    DismissFileError,
+    ToggleDiff,
+    OverwriteFile,
+    ToggleBreakpoint(usize),
     // these messages are only sent when embedded; and are handled in
     // the main window
     RunAllTask,
     RunTask,
+    RunLines(Range<usize>),
+    RunCell,
+    RunCellAdvance,
+    RunAbove,
+    RunBelow,
+    Debug,
     SearchHelp,
     HelpTask,
 }
 
+impl Message {
+    /// Whether the message changes the buffer
+    fn is_edit(&self) -> bool {
+        match self {
+            Message::EditorAction(action) => action.is_edit(),
+            Message::NewFile
+            | Message::OpenFile
+            | Message::Comment
+            | Message::InsertText(..)
+            | Message::AcceptCompletion(_)
+            | Message::ReplaceOne
+            | Message::ReplaceAll
+            | Message::Undo
+            | Message::Redo
+            | Message::Lines(_)
+            | Message::Format
+            | Message::ReloadFile => true,
+            _ => false,
+        }
+    }
+}
+
 impl Editor {
     pub fn embed(mut self) -> Self {
         self.embedded = true;
@@ -65,32 +248,262 @@ impl Editor {
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
+        if self.read_only && message.is_edit() {
+            return Task::none();
+        }
         match message {
             Message::ThemeChange(theme) => {
                 self.theme = theme;
                 Task::none()
             }
+            Message::ToggleRainbow(rainbow) => {
+                self.rainbow = rainbow;
+                Task::none()
+            }
             Message::FuncAtMark(func) => {
-                // todo get signature from the actual function
-                self.signature = func
-                    .as_ref()
-                    .map(|(t, n)| format!("{t} {n}"))
-                    .unwrap_or_default();
                 self.function = func;
+                self.active_arg = self.active_argument();
+                Task::none()
+            }
+            Message::EditorAction(text_editor::Action::Scroll { lines }) => {
+                self.content.perform(text_editor::Action::Scroll { lines });
+                self.scroll += lines as f32 * LINE_HEIGHT;
+                self.follow_scroll();
                 Task::none()
             }
             Message::EditorAction(action) => {
-                self.is_dirty = self.is_dirty || action.is_edit();
+                let edit = action.is_edit();
+                self.is_dirty = self.is_dirty || edit;
+                if edit {
+                    self.record(history::EditKind::from_action(&action));
+                } else {
+                    self.history.break_group();
+                }
+                // keep completing while typing, close it otherwise
+                let complete = match &action {
+                    text_editor::Action::Edit(text_editor::Edit::Insert(c)) => {
+                        !self.completions.is_empty()
+                            || c.is_alphanumeric()
+                            || matches!(c, '_' | '.' | '(' | '[' | ',')
+                    }
+                    text_editor::Action::Edit(text_editor::Edit::Backspace) => {
+                        !self.completions.is_empty()
+                    }
+                    _ => false,
+                };
+                let old = (edit && self.has_markers()).then(|| self.line_texts());
+                // a new selection starts from the cursor
+                if self.content.selection().is_none() {
+                    self.select_anchor = self.content.cursor_position();
+                }
                 self.content.perform(action);
-                Task::perform(
-                    task_at_mark(self.content.text(), self.content.cursor_position()),
-                    Message::FuncAtMark,
-                )
+                if let Some(old) = old {
+                    self.shift_markers(&old);
+                }
+                if complete {
+                    self.complete();
+                } else {
+                    self.completions.clear();
+                }
+                if edit { self.changed() } else { self.moved() }
+            }
+            Message::InsertText(txt, select) => {
+                let len = txt.chars().count();
+                self.is_dirty = true;
+                self.record(history::EditKind::Other);
+                self.content
+                    .perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+                        Arc::new(txt),
+                    )));
+                if let Some(sel) = select {
+                    for _ in sel.end..len {
+                        self.content
+                            .perform(text_editor::Action::Move(text_editor::Motion::Left));
+                    }
+                    self.select_anchor = self.content.cursor_position();
+                    for _ in sel {
+                        self.content
+                            .perform(text_editor::Action::Select(text_editor::Motion::Left));
+                    }
+                }
+                self.changed()
+            }
+            Message::Complete => {
+                self.complete();
+                Task::none()
+            }
+            Message::CompletionMove(up) => {
+                let n = self.completions.len().max(1);
+                self.completion_sel = if up {
+                    (self.completion_sel + n - 1) % n
+                } else {
+                    (self.completion_sel + 1) % n
+                };
+                Task::none()
+            }
+            Message::AcceptCompletion(ind) => {
+                if let Some(comp) = self.completions.get(ind).cloned() {
+                    self.record(history::EditKind::Other);
+                    for _ in 0..self.completion_len {
+                        self.content
+                            .perform(text_editor::Action::Select(text_editor::Motion::Left));
+                    }
+                    self.content
+                        .perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+                            Arc::new(comp.insert),
+                        )));
+                    self.is_dirty = true;
+                }
+                self.completions.clear();
+                self.changed()
+            }
+            Message::CloseCompletion => {
+                self.completions.clear();
+                Task::none()
+            }
+            Message::CheckDiagnostics => self.check_diagnostics(),
+            Message::Diagnostics(report) => {
+                self.diagnostics = report.diagnostics;
+                self.brackets = report.brackets;
+                // the old outline doesn't match the lines with errors
+                self.outline = report.outline.unwrap_or_default();
+                Task::none()
+            }
+            Message::GotoPosition(line, col) => {
+                self.goto(line, col);
+                self.moved()
+            }
+            Message::OpenFind => {
+                if self.find.is_none() {
+                    // start with the selected text
+                    let query = self
+                        .content
+                        .selection()
+                        .filter(|s| !s.contains('\n'))
+                        .unwrap_or_default();
+                    self.find = Some(find::Find {
+                        query,
+                        ..Default::default()
+                    });
+                    self.refresh_find(&self.content.text());
+                }
+                text_input::focus(self.find_id.clone())
+            }
+            Message::CloseFind => {
+                self.find = None;
+                Task::none()
             }
+            Message::FindChange(q) => {
+                if let Some(f) = &mut self.find {
+                    f.query = q;
+                }
+                self.refresh_find(&self.content.text());
+                self.select_match()
+            }
+            Message::ReplaceChange(r) => {
+                if let Some(f) = &mut self.find {
+                    f.replacement = r;
+                }
+                Task::none()
+            }
+            Message::FindToggle(opt) => {
+                if let Some(f) = &mut self.find {
+                    f.toggle(opt);
+                }
+                self.refresh_find(&self.content.text());
+                self.select_match()
+            }
+            Message::FindNext | Message::FindPrevious => {
+                if let Some(f) = &mut self.find {
+                    if matches!(message, Message::FindNext) {
+                        f.next();
+                    } else {
+                        f.previous();
+                    }
+                }
+                self.select_match()
+            }
+            Message::ReplaceOne => {
+                let text = self.content.text();
+                let Some(f) = &self.find else {
+                    return Task::none();
+                };
+                let Some(range) = f.current_match(&text) else {
+                    return Task::none();
+                };
+                // select the match first, so the users can see what
+                // they are replacing
+                if self.content.selection().as_deref() != Some(&text[range]) {
+                    return self.select_match();
+                }
+                let Some(rep) = f.replacement_for(&text) else {
+                    return Task::none();
+                };
+                self.record(history::EditKind::Other);
+                self.content
+                    .perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+                        Arc::new(rep),
+                    )));
+                self.is_dirty = true;
+                let changed = self.changed();
+                Task::batch([changed, self.select_match()])
+            }
+            Message::ReplaceAll => {
+                let text = self.content.text();
+                let Some(new) = self.find.as_ref().and_then(|f| f.replace_all(&text)) else {
+                    return Task::none();
+                };
+                if new == text {
+                    return Task::none();
+                }
+                let (line, col) = self.cursor_chars();
+                self.replace_text(new);
+                self.goto(line, col);
+                self.changed()
+            }
+            Message::Format => {
+                let Some(nft) = self.formattable() else {
+                    return Task::none();
+                };
+                let text = self.content.text();
+                // errors are already shown in the diagnostics
+                match format::format(&text, &nft) {
+                    Ok(new) if new != text => {
+                        let (line, col) = self.cursor_chars();
+                        self.replace_text(new);
+                        self.goto(line, col);
+                        self.changed()
+                    }
+                    _ => Task::none(),
+                }
+            }
+            Message::Comment => self.line_command(lines::LineCommand::ToggleComment),
+            Message::Lines(cmd) => self.line_command(cmd),
+            Message::Undo => match self.history.undo(self.snapshot()) {
+                Some(snap) => self.restore(snap),
+                None => Task::none(),
+            },
+            Message::Redo => match self.history.redo(self.snapshot()) {
+                Some(snap) => self.restore(snap),
+                None => Task::none(),
+            },
             Message::NewFile => {
                 if !self.is_loading {
                     self.file = None;
-                    self.content = text_editor::Content::new();
+                    self.set_content("");
+                    self.mark_saved();
+                    self.diagnostics.clear();
+                    self.history.clear();
+                    self.executed.clear();
+                    self.breakpoints.clear();
+                    self.modified = None;
+                    self.polled = None;
+                    self.conflict = None;
+                    self.file_error = None;
+                    self.outline.clear();
+                    self.brackets.clear();
+                    self.cells = cells::cells("");
+                    self.refresh_find(&self.content.text());
                 }
                 Task::none()
             }
@@ -110,43 +523,45 @@ impl Editor {
                         if let Some(p) = path.parent() {
                             let _ = std::env::set_current_dir(p);
                         }
+                        self.modified = file_modified(&path);
+                        self.polled = self.modified;
+                        self.conflict = None;
+                        self.file_error = None;
                         self.file = Some(path);
-                        self.content = text_editor::Content::with_text(&contents);
+                        self.set_content(&contents);
+                        self.mark_saved();
+                        self.history.clear();
+                        self.executed.clear();
+                        self.breakpoints.clear();
                     }
                     Err(e) => {
                         println!("{e:?}")
                     }
                 };
-                Task::none()
+                self.changed()
             }
-            Message::SaveFile => {
-                if self.is_loading {
-                    Task::none()
-                } else {
-                    self.is_loading = true;
-
-                    let mut text = self.content.text();
-
-                    // // only on 0.14
-                    // if let Some(ending) = self.content.line_ending() {
-                    //     if !text.ends_with(ending.as_str()) {
-                    //         text.push_str(ending.as_str());
-                    //     }
-                    // }
-                    if !text.ends_with('\n') {
-                        text.push('\n');
-                    }
-
-                    Task::perform(save_file(self.file.clone(), text), Message::FileSaved)
-                }
+            Message::SaveFile => self.save(self.modified),
+            Message::OverwriteFile => {
+                self.conflict = None;
+                self.save(None)
             }
             Message::FileSaved(result) => {
                 self.is_loading = false;
 
                 match result {
                     Ok(path) => {
+                        self.modified = file_modified(&path);
+                        self.polled = self.modified;
                         self.file = Some(path);
-                        self.is_dirty = false;
+                        self.mark_saved();
+                    }
+                    Err(Error::Modified(disk)) => {
+                        self.polled = self.file.as_deref().and_then(file_modified);
+                        self.conflict = Some(Conflict {
+                            disk: disk.to_string(),
+                            diff: None,
+                            saving: true,
+                        });
                     }
                     Err(e) => {
                         println!("{e:?}")
@@ -154,6 +569,72 @@ impl Editor {
                 }
                 Task::none()
             }
+            Message::CheckFile => {
+                let Some(path) = &self.file else {
+                    return Task::none();
+                };
+                let modified = file_modified(path);
+                if self.is_loading || modified.is_none() || modified == self.polled {
+                    return Task::none();
+                }
+                self.polled = modified;
+                Task::perform(load_file(path.clone()), Message::FileChanged)
+            }
+            Message::FileChanged(result) => match result {
+                Ok((_, contents)) => {
+                    self.file_error = None;
+                    if *contents == self.content.text() {
+                        self.modified = self.polled;
+                        self.conflict = None;
+                        Task::none()
+                    } else if self.is_dirty || self.read_only {
+                        self.conflict = Some(Conflict {
+                            disk: contents.to_string(),
+                            diff: None,
+                            saving: false,
+                        });
+                        Task::none()
+                    } else {
+                        self.reload(&contents)
+                    }
+                }
+                Err(e) => {
+                    self.file_error = Some(match e {
+                        Error::IoError(kind) => format!("Could not read the file on disk: {kind}"),
+                        e => format!("Could not read the file on disk: {e:?}"),
+                    });
+                    Task::none()
+                }
+            },
+            Message::DismissFileError => {
+                self.file_error = None;
+                Task::none()
+            }
+            Message::ReloadFile => match self.conflict.take() {
+                Some(c) => self.reload(&c.disk),
+                None => Task::none(),
+            },
+            Message::KeepMine => {
+                // the file is overwritten on the next save
+                self.modified = self.polled;
+                self.conflict = None;
+                Task::none()
+            }
+            Message::ToggleBreakpoint(line) => {
+                if !self.breakpoints.remove(&line) {
+                    self.breakpoints.insert(line);
+                }
+                Task::none()
+            }
+            Message::ToggleDiff => {
+                if let Some(c) = &mut self.conflict {
+                    c.diff = match c.diff {
+                        Some(_) => None,
+                        None => Some(diff::line_diff(&self.content.text(), &c.disk)),
+                    };
+                }
+                Task::none()
+            }
             // remaining ones should be handled in main window, and
             // should be absent during non embed status; type system
             // can't help here, so be careful
@@ -170,7 +651,26 @@ impl Editor {
                 "Save",
                 self.is_dirty.then_some(Message::SaveFile)
             ),
-            icons::action(icons::comment_icon(), "Comment", Some(Message::Comment)),
+            icons::action(
+                icons::comment_icon(),
+                "Toggle Comment",
+                lines::comment_prefix(self.extension()).map(|_| Message::Comment)
+            ),
+            icons::action(
+                icons::pencil_icon(),
+                "Format",
+                self.formattable().map(|_| Message::Format)
+            ),
+            icons::action(
+                icons::undo_icon(),
+                "Undo",
+                self.history.can_undo().then_some(Message::Undo)
+            ),
+            icons::action(
+                icons::redo_icon(),
+                "Redo",
+                self.history.can_redo().then_some(Message::Redo)
+            ),
         ];
         if self.embedded {
             controls = controls
@@ -185,6 +685,31 @@ impl Editor {
                     "Run Buffer",
                     Some(Message::RunAllTask),
                 ))
+                .push(icons::action(
+                    icons::run_line_icon(),
+                    "Run Cell",
+                    Some(Message::RunCell),
+                ))
+                .push(icons::action(
+                    icons::run_step_icon(),
+                    "Run Cell and Advance",
+                    Some(Message::RunCellAdvance),
+                ))
+                .push(icons::action(
+                    icons::debug_icon(),
+                    "Debug Buffer (F5)",
+                    Some(Message::Debug),
+                ))
+                .push(icons::action(
+                    icons::top_icon(),
+                    "Run All Above Cursor",
+                    Some(Message::RunAbove),
+                ))
+                .push(icons::action(
+                    icons::bottom_icon(),
+                    "Run From Cursor to End",
+                    Some(Message::RunBelow),
+                ))
                 .push(icons::action(
                     icons::search_icon(),
                     "Search in Help Window",
@@ -197,13 +722,20 @@ impl Editor {
                 ));
         }
         controls = controls.push(horizontal_space());
+        controls = controls.push(
+            toggler(self.rainbow)
+                .label("Rainbow")
+                .on_toggle(Message::ToggleRainbow),
+        );
         controls = controls.push(pick_list(
             highlighter::Theme::ALL,
             Some(self.theme),
             Message::ThemeChange,
         ));
 
-        let signature = row![text(self.signature.clone())];
+        let signature = self.signature_view();
+        let find_bar = self.find_view();
+        let (cur_line, _) = self.content.cursor_position();
         let status = row![
             text(
                 self.file
@@ -211,31 +743,1165 @@ impl Editor {
                     .map(|p| { p.to_string_lossy().to_string() })
                     .unwrap_or("*New File*".into())
             ),
+            text(if self.read_only {
+                " (read only while debugging)"
+            } else {
+                ""
+            })
+            .style(text::secondary),
+            horizontal_space(),
+            // problems in the current line
+            text(
+                self.diagnostics
+                    .iter()
+                    .filter(|d| d.line == cur_line)
+                    .map(|d| d.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            )
+            .style(text::danger),
             horizontal_space(),
             text({
-                let (line, column) = self.content.cursor_position();
+                let (line, column) = self.cursor_chars();
                 format!("{}:{}", line + 1, column + 1)
             })
         ];
+        let completing = !self.completions.is_empty();
+        let editor = responsive(|size| self.editor_view(size));
+        let editor: Element<_> = if completing {
+            stack![
+                editor,
+                container(self.completion_view())
+                    .align_right(Fill)
+                    .align_bottom(Fill)
+                    .padding(10)
+            ]
+            .into()
+        } else {
+            editor.into()
+        };
+        column![
+            controls.spacing(10).height(30.0),
+            self.conflict_view(),
+            find_bar,
+            signature,
+            editor,
+            self.problems_view(),
+            status
+        ]
+        .padding(10)
+        .into()
+    }
+
+    /// Editor along with the gutter, the gutter shows the lines
+    /// visible in the editor
+    fn editor_view(&self, size: iced::Size) -> Element<'_, Message> {
+        let completing = !self.completions.is_empty();
+        let selected = self.completion_sel;
+        let multiline = self.content.selection().is_some_and(|s| s.contains('\n'));
+        let embedded = self.embedded;
+        let (cur_line, _) = self.content.cursor_position();
+        self.text_height
+            .set((size.height - 2.0 * EDITOR_PADDING).max(0.0));
         let editor = text_editor(&self.content)
             .height(Fill)
+            .padding(EDITOR_PADDING)
+            .size(TEXT_SIZE)
+            .line_height(Pixels(LINE_HEIGHT))
+            .wrapping(text::Wrapping::None)
             .on_action(Message::EditorAction)
+            .key_binding(move |kp| {
+                if kp.status != text_editor::Status::Focused {
+                    return None;
+                }
+                let custom = |m| Some(text_editor::Binding::Custom(m));
+                match kp.key.as_ref() {
+                    Key::Character(" ") if kp.modifiers.command() => custom(Message::Complete),
+                    Key::Character("f" | "F") if kp.modifiers.command() && kp.modifiers.shift() => {
+                        custom(Message::Format)
+                    }
+                    Key::Character("f") if kp.modifiers.command() => custom(Message::OpenFind),
+                    Key::Character("z" | "Z") if kp.modifiers.command() => {
+                        if kp.modifiers.shift() {
+                            custom(Message::Redo)
+                        } else {
+                            custom(Message::Undo)
+                        }
+                    }
+                    Key::Character("y") if kp.modifiers.command() => custom(Message::Redo),
+                    Key::Character("/") if kp.modifiers.command() => custom(Message::Comment),
+                    Key::Character("d") if kp.modifiers.command() => {
+                        custom(Message::Lines(lines::LineCommand::Duplicate))
+                    }
+                    Key::Character("]") if kp.modifiers.command() => {
+                        custom(Message::Lines(lines::LineCommand::Indent))
+                    }
+                    Key::Character("[") if kp.modifiers.command() => {
+                        custom(Message::Lines(lines::LineCommand::Dedent))
+                    }
+                    Key::Named(Named::Tab) if kp.modifiers.shift() => {
+                        custom(Message::Lines(lines::LineCommand::Dedent))
+                    }
+                    Key::Named(Named::Tab) if multiline => {
+                        custom(Message::Lines(lines::LineCommand::Indent))
+                    }
+                    Key::Named(Named::ArrowUp) if kp.modifiers.alt() => {
+                        custom(Message::Lines(lines::LineCommand::MoveUp))
+                    }
+                    Key::Named(Named::ArrowDown) if kp.modifiers.alt() => {
+                        custom(Message::Lines(lines::LineCommand::MoveDown))
+                    }
+                    Key::Named(Named::Enter) if embedded && kp.modifiers.command() => {
+                        custom(Message::RunCell)
+                    }
+                    Key::Named(Named::Enter) if embedded && kp.modifiers.shift() => {
+                        custom(Message::RunCellAdvance)
+                    }
+                    Key::Named(Named::F5) if embedded => custom(Message::Debug),
+                    Key::Named(Named::F9) if embedded => {
+                        custom(Message::ToggleBreakpoint(cur_line))
+                    }
+                    Key::Named(Named::Tab | Named::Enter) if completing => {
+                        custom(Message::AcceptCompletion(selected))
+                    }
+                    Key::Named(Named::ArrowUp) if completing => {
+                        custom(Message::CompletionMove(true))
+                    }
+                    Key::Named(Named::ArrowDown) if completing => {
+                        custom(Message::CompletionMove(false))
+                    }
+                    Key::Named(Named::Escape) if completing => custom(Message::CloseCompletion),
+                    _ => text_editor::Binding::from_key_press(kp),
+                }
+            })
             .font(Font::MONOSPACE);
-        let ext = self
-            .file
-            .as_deref()
-            .and_then(Path::extension)
-            .and_then(std::ffi::OsStr::to_str)
-            .unwrap_or("tasks");
+        let ext = self.extension();
         let editor: Element<_> = match my_hl::NadiFileType::from_str(ext) {
             // use custom highlights for nadi files
-            Ok(nft) => editor
-                .highlight_with::<my_hl::NadiHighlighter>(nft, my_hl::Highlight::to_format)
-                .into(),
+            Ok(nft) => {
+                let errors = self
+                    .diagnostics
+                    .iter()
+                    .filter_map(|d| {
+                        let line = self.content.line(d.line)?;
+                        Some((d.line, d.byte_range(&line)))
+                    })
+                    .collect();
+                editor
+                    .highlight_with::<my_hl::NadiHighlighter>(
+                        my_hl::Settings {
+                            nft,
+                            errors,
+                            theme: Some(self.theme),
+                            names: self.symbols.names(),
+                            brackets: self.matching_brackets(),
+                            rainbow: self.rainbow,
+                        },
+                        my_hl::Styled::to_format,
+                    )
+                    .into()
+            }
             _ => editor.highlight(ext, self.theme).into(),
         };
-        column![controls.spacing(10).height(30.0), signature, editor, status]
-            .padding(10)
+        row![
+            self.gutter_view(),
+            stack![editor, self.cell_rules(), self.debug_line_view()]
+        ]
+        .height(Fill)
+        .into()
+    }
+
+    /// Scroll of the editor for its current height, the editor
+    /// doesn't scroll past the last line
+    fn scroll_offset(&self) -> f32 {
+        let total = self.content.line_count() as f32 * LINE_HEIGHT;
+        self.scroll.min(total - self.text_height.get()).max(0.0)
+    }
+
+    /// Follow the scroll of the editor after the actions, it keeps
+    /// the cursor visible when the cursor moves
+    fn follow_scroll(&mut self) {
+        let cursor = self.content.cursor_position();
+        if cursor != self.scroll_cursor {
+            self.scroll_cursor = cursor;
+            let y = cursor.0 as f32 * LINE_HEIGHT;
+            if y < self.scroll {
+                self.scroll = y;
+            } else if y + LINE_HEIGHT > self.scroll + self.text_height.get() {
+                self.scroll = y + LINE_HEIGHT - self.text_height.get();
+            }
+        }
+        self.scroll = self.scroll_offset();
+    }
+
+    /// Replace the buffer with a new one, scrolled to the top
+    fn set_content(&mut self, text: &str) {
+        self.content = text_editor::Content::with_text(text);
+        self.scroll = 0.0;
+        self.scroll_cursor = (0, 0);
+    }
+
+    /// Lines visible in the editor, and the pixels of the first one
+    /// that are scrolled out of view
+    fn visible_lines(&self) -> (Range<usize>, f32) {
+        let scroll = self.scroll_offset();
+        let first = (scroll / LINE_HEIGHT) as usize;
+        let last = ((scroll + self.text_height.get()) / LINE_HEIGHT).ceil() as usize;
+        (
+            first..last.min(self.content.line_count()),
+            scroll - first as f32 * LINE_HEIGHT,
+        )
+    }
+
+    /// Lines and byte ranges of the bracket at the cursor and its
+    /// match
+    fn matching_brackets(&self) -> Vec<(usize, Range<usize>)> {
+        let (line, col) = self.cursor_chars();
+        let Some(pair) = brackets::matching(&self.brackets, (line, col)) else {
+            return vec![];
+        };
+        pair.iter()
+            .filter_map(|b| {
+                let line = self.content.line(b.line)?;
+                let (start, ch) = line.char_indices().nth(b.col)?;
+                Some((b.line, start..(start + ch.len_utf8())))
+            })
+            .collect()
+    }
+
+    /// Whether the buffer is divided into cells with the markers
+    fn has_cells(&self) -> bool {
+        self.cells.len() > 1 || self.content.line(0).is_some_and(|l| cells::is_marker(&l))
+    }
+
+    /// Line range of the cell at the cursor
+    pub fn current_cell(&self) -> Range<usize> {
+        let (line, _) = self.content.cursor_position();
+        cells::cell_at(&self.cells, line)
+    }
+
+    /// Lines separating the cells, drawn over the editor at the top
+    /// of the marker lines
+    fn cell_rules(&self) -> Element<'_, Message> {
+        let mut rules = Column::new().width(Fill);
+        if !self.has_cells() {
+            return rules.into();
+        }
+        let scroll = self.scroll_offset();
+        let bottom = EDITOR_PADDING + self.text_height.get();
+        let mut top = 0.0;
+        for cell in &self.cells {
+            let y = EDITOR_PADDING + cell.start as f32 * LINE_HEIGHT - scroll;
+            if !(EDITOR_PADDING..=bottom).contains(&y) {
+                continue;
+            }
+            rules = rules.push(vertical_space().height(y - top)).push(
+                container(horizontal_space())
+                    .width(Fill)
+                    .height(1.0)
+                    .style(|theme: &Theme| {
+                        container::background(theme.extended_palette().primary.weak.color)
+                    }),
+            );
+            top = y + 1.0;
+        }
+        rules.into()
+    }
+
+    /// Line the debugger is paused at, drawn over the editor
+    fn debug_line_view(&self) -> Element<'_, Message> {
+        let Some(line) = self.debug_line else {
+            return column![].into();
+        };
+        // only the part inside the editor
+        let y = line as f32 * LINE_HEIGHT - self.scroll_offset();
+        let top = y.max(0.0);
+        let bottom = (y + LINE_HEIGHT).min(self.text_height.get());
+        if bottom <= top {
+            return column![].into();
+        }
+        column![
+            vertical_space().height(EDITOR_PADDING + top),
+            container(horizontal_space())
+                .width(Fill)
+                .height(bottom - top)
+                .style(|theme: &Theme| {
+                    container::background(
+                        theme.extended_palette().primary.base.color.scale_alpha(0.2),
+                    )
+                })
+        ]
+        .into()
+    }
+
+    /// Line the debugger is paused at
+    pub fn debug_line(&self) -> Option<usize> {
+        self.debug_line
+    }
+
+    pub fn set_debug_line(&mut self, line: Option<usize>) {
+        self.debug_line = line;
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub fn breakpoints(&self) -> &BTreeSet<usize> {
+        &self.breakpoints
+    }
+
+    /// Marker for the first line of the cells, showing whether the
+    /// code in the cell has been run
+    fn cell_marker(&self, cell: &Range<usize>) -> Element<'_, Message> {
+        let code: Vec<usize> = cell
+            .clone()
+            .filter(|&l| self.content.line(l).is_some_and(|l| cells::is_code(&l)))
+            .collect();
+        let run = code.iter().filter(|l| self.executed.contains(l)).count();
+        let (mark, msg, style): (_, _, fn(&Theme) -> text::Style) = if code.is_empty() {
+            return horizontal_space().width(MARKER_WIDTH).into();
+        } else if run == code.len() {
+            ("●", "Cell has been run", text::success)
+        } else if run > 0 {
+            ("●", "Cell has been partly run", text::primary)
+        } else {
+            ("○", "Cell has not been run", text::secondary)
+        };
+        tooltip(
+            text(mark)
+                .size(TEXT_SIZE)
+                .line_height(Pixels(LINE_HEIGHT))
+                .style(style),
+            container(text(msg))
+                .padding(5)
+                .style(container::rounded_box),
+            tooltip::Position::Right,
+        )
+        .into()
+    }
+
+    fn gutter_width(&self) -> f32 {
+        let digits = self.content.line_count().max(1).to_string().len();
+        EXEC_WIDTH + (digits + 1) as f32 * CHAR_WIDTH + MARKER_WIDTH
+    }
+
+    /// Line numbers with the markers for the lines that have been run
+    /// and the ones with problems, the message is shown when hovering
+    /// over them
+    fn gutter_view(&self) -> Element<'_, Message> {
+        let (cur_line, _) = self.content.cursor_position();
+        let mut diags: BTreeMap<usize, Vec<&Diagnostic>> = BTreeMap::new();
+        for d in &self.diagnostics {
+            diags.entry(d.line).or_default().push(d);
+        }
+        let number_width = self.gutter_width() - EXEC_WIDTH - MARKER_WIDTH;
+        let cell_starts: BTreeMap<usize, &Range<usize>> = if self.has_cells() {
+            self.cells.iter().map(|c| (c.start, c)).collect()
+        } else {
+            BTreeMap::new()
+        };
+        let mut gutter = Column::new()
+            .width(self.gutter_width())
+            .padding(Padding::ZERO.top(EDITOR_PADDING));
+        let (visible, hidden) = self.visible_lines();
+        for line in visible.clone() {
+            let executed = self.executed.contains(&line);
+            let current = line == cur_line;
+            let paused = self.debug_line == Some(line);
+            let breakpoint = self.breakpoints.contains(&line);
+            let marker: Element<_> = match diags.get(&line) {
+                Some(line_diags) => {
+                    let msg: Vec<String> = line_diags.iter().map(|d| d.summary()).collect();
+                    let severity = if line_diags.iter().any(|d| d.severity == Severity::Error) {
+                        Severity::Error
+                    } else {
+                        Severity::Warning
+                    };
+                    tooltip(
+                        text("●")
+                            .size(TEXT_SIZE)
+                            .line_height(Pixels(LINE_HEIGHT))
+                            .color(severity_color(severity)),
+                        container(text(msg.join("\n")))
+                            .padding(5)
+                            .style(container::rounded_box),
+                        tooltip::Position::Right,
+                    )
+                    .into()
+                }
+                None => match cell_starts.get(&line) {
+                    Some(cell) => self.cell_marker(cell),
+                    None => horizontal_space().width(MARKER_WIDTH).into(),
+                },
+            };
+            let number = container(row![
+                container(horizontal_space())
+                    .width(EXEC_WIDTH)
+                    .height(LINE_HEIGHT)
+                    .style(move |theme: &Theme| if executed {
+                        container::background(theme.extended_palette().success.base.color)
+                    } else {
+                        container::Style::default()
+                    }),
+                self.line_number(line, number_width, current, breakpoint),
+                container(marker).width(MARKER_WIDTH).center_x(MARKER_WIDTH),
+            ])
+            .height(LINE_HEIGHT)
+            .style(move |theme: &Theme| {
+                if paused {
+                    container::background(theme.extended_palette().primary.weak.color)
+                } else if current {
+                    container::background(theme.extended_palette().background.weak.color)
+                } else {
+                    container::Style::default()
+                }
+            });
+            // first line can be partly scrolled out of view
+            gutter = if line == visible.start && hidden > 0.0 {
+                gutter.push(
+                    container(number)
+                        .align_bottom(LINE_HEIGHT - hidden)
+                        .clip(true),
+                )
+            } else {
+                gutter.push(number)
+            };
+        }
+        container(gutter).height(Fill).clip(true).into()
+    }
+
+    /// Line number in the gutter, clicking on it toggles the
+    /// breakpoint when embedded
+    fn line_number(
+        &self,
+        line: usize,
+        width: f32,
+        current: bool,
+        breakpoint: bool,
+    ) -> Element<'_, Message> {
+        let number = text(line + 1)
+            .size(TEXT_SIZE)
+            .line_height(Pixels(LINE_HEIGHT))
+            .font(Font::MONOSPACE)
+            .width(Fill)
+            .align_x(iced::alignment::Horizontal::Right)
+            .style(if breakpoint {
+                // color from the button
+                text::default
+            } else if current {
+                text::primary
+            } else {
+                text::secondary
+            });
+        if !self.embedded {
+            return container(number).width(width).into();
+        }
+        button(number)
+            .on_press(Message::ToggleBreakpoint(line))
+            .padding(0)
+            .width(width)
+            .style(move |theme: &Theme, status| {
+                if breakpoint {
+                    let danger = theme.extended_palette().danger.base;
+                    button::Style {
+                        background: Some(danger.color.into()),
+                        text_color: danger.text,
+                        border: iced::border::rounded(LINE_HEIGHT / 2.0),
+                        ..Default::default()
+                    }
+                } else {
+                    button::text(theme, status)
+                }
+            })
+            .into()
+    }
+
+    /// Lines in the selection, or the current line if nothing is
+    /// selected
+    pub fn selected_lines(&self) -> Range<usize> {
+        let cursor = self.content.cursor_position();
+        let line = cursor.0;
+        let Some(sel) = self.content.selection() else {
+            return line..(line + 1);
+        };
+        let lines = sel.matches('\n').count();
+        // cursor is at the end if it moved forward from the anchor
+        if self.select_anchor <= cursor {
+            line.saturating_sub(lines)..(line + 1)
+        } else {
+            line..(line + lines + 1)
+        }
+    }
+
+    /// Mark the lines as executed in the gutter
+    pub fn mark_executed(&mut self, lines: Range<usize>) {
+        self.executed.extend(lines);
+    }
+
+    fn has_markers(&self) -> bool {
+        !self.executed.is_empty() || !self.breakpoints.is_empty()
+    }
+
+    fn line_texts(&self) -> Vec<String> {
+        self.content.lines().map(|l| l.to_string()).collect()
+    }
+
+    /// Move the executed markers and the breakpoints with the lines
+    /// after an edit, the edited lines are the ones that differ from
+    /// the old lines at the start and at the end. The markers on the
+    /// removed lines (e.g. joined with the line above) move to the
+    /// last edited line.
+    fn shift_markers(&mut self, old: &[String]) {
+        let new_count = self.content.line_count();
+        if new_count == old.len() {
+            return;
+        }
+        let same = |o: &String, n: usize| self.content.line(n).is_some_and(|l| *l == **o);
+        let prefix = old
+            .iter()
+            .enumerate()
+            .take_while(|(i, l)| same(l, *i))
+            .count();
+        let suffix = (1..=(old.len() - prefix).min(new_count - prefix))
+            .take_while(|&i| same(&old[old.len() - i], new_count - i))
+            .count();
+        let removed = prefix..(old.len() - suffix);
+        let added = new_count - suffix - prefix;
+        self.map_markers(|l| {
+            if l < removed.start {
+                Some(l)
+            } else if l >= removed.end {
+                Some(l - removed.end + removed.start + added)
+            } else {
+                (added > 0).then(|| l.min(removed.start + added - 1))
+            }
+        });
+    }
+
+    /// Move the executed markers and the breakpoints, the ones mapped
+    /// to None are removed
+    fn map_markers(&mut self, map: impl Fn(usize) -> Option<usize>) {
+        self.executed = self.executed.iter().filter_map(|&l| map(l)).collect();
+        self.breakpoints = self.breakpoints.iter().filter_map(|&l| map(l)).collect();
+    }
+
+    fn find_view(&self) -> Element<'_, Message> {
+        let Some(f) = &self.find else {
+            return row![].into();
+        };
+        let has_matches = !f.matches.is_empty();
+        let toggle = |label, opt, tip| {
+            tooltip(
+                button(text(label).font(Font::MONOSPACE))
+                    .on_press(Message::FindToggle(opt))
+                    .padding([2, 5])
+                    .style(if f.is_set(opt) {
+                        button::primary
+                    } else {
+                        button::secondary
+                    }),
+                tip,
+                tooltip::Position::Bottom,
+            )
+        };
+        row![
+            text_input("Find", &f.query)
+                .id(self.find_id.clone())
+                .on_input(Message::FindChange)
+                .on_submit(Message::FindNext)
+                .padding(5)
+                .width(200),
+            toggle("Aa", find::FindOption::CaseSensitive, "Match Case"),
+            toggle("W", find::FindOption::WholeWord, "Whole Word"),
+            toggle(".*", find::FindOption::Regex, "Regular Expression"),
+            text(f.status()).style(if f.error.is_some() {
+                text::danger
+            } else {
+                text::default
+            }),
+            icons::action(
+                icons::arrow_up_icon(),
+                "Previous",
+                has_matches.then_some(Message::FindPrevious)
+            ),
+            icons::action(
+                icons::arrow_down_icon(),
+                "Next",
+                has_matches.then_some(Message::FindNext)
+            ),
+            text_input("Replace", &f.replacement)
+                .on_input(Message::ReplaceChange)
+                .on_submit(Message::ReplaceOne)
+                .padding(5)
+                .width(200),
+            button("Replace").on_press_maybe(has_matches.then_some(Message::ReplaceOne)),
+            button("Replace All").on_press_maybe(has_matches.then_some(Message::ReplaceAll)),
+            horizontal_space(),
+            icons::action(icons::cancel_icon(), "Close", Some(Message::CloseFind)),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+        .into()
+    }
+
+    /// Search the buffer again for the find query, the current match
+    /// is the one at the cursor or the selection
+    fn refresh_find(&mut self, text: &str) {
+        let cursor = self.cursor_chars();
+        let Some(f) = &mut self.find else {
+            return;
+        };
+        let cursor = find::offset(text, cursor);
+        // cursor is at the end of the selection after selecting a match
+        let selected = self.content.selection().map(|s| s.len()).unwrap_or(0);
+        f.search(text, cursor.saturating_sub(selected));
+    }
+
+    /// Select the current match in the editor
+    fn select_match(&mut self) -> Task<Message> {
+        let text = self.content.text();
+        let Some(range) = self.find.as_ref().and_then(|f| f.current_match(&text)) else {
+            return Task::none();
+        };
+        let (line, col) = find::position(&text, range.start);
+        self.goto(line, col);
+        self.select_anchor = self.content.cursor_position();
+        for _ in text[range].chars() {
+            self.content
+                .perform(text_editor::Action::Select(text_editor::Motion::Right));
+        }
+        self.moved()
+    }
+
+    /// List of the problems in the buffer, clicking on them moves the
+    /// cursor there
+    fn problems_view(&self) -> Element<'_, Message> {
+        let mut list = Column::new();
+        for d in &self.diagnostics {
+            list = list.push(
+                button(text(d.summary()).size(14).color(severity_color(d.severity)))
+                    .on_press(Message::GotoPosition(d.line, d.cols.start))
+                    .padding([2, 5])
+                    .width(Fill)
+                    .style(button::text),
+            );
+        }
+        scrollable(list).height(Length::Shrink).into()
+    }
+
+    /// Tasks and sections of the tasks file, clicking on them moves
+    /// the cursor there
+    pub fn outline_view(&self) -> Element<'_, Message> {
+        if self.outline.is_empty() {
+            let msg = if self.diagnostics.is_empty() {
+                "No tasks in the buffer"
+            } else {
+                "Fix the problems in the buffer to see the outline"
+            };
+            return container(text(msg).style(text::secondary))
+                .center(Fill)
+                .into();
+        }
+        let (cur_line, _) = self.content.cursor_position();
+        let mut list = Column::new().spacing(2);
+        let mut in_section = false;
+        for item in &self.outline {
+            let (entry, indent): (Element<_>, f32) = match &item.kind {
+                outline::OutlineKind::Section => {
+                    in_section = true;
+                    (
+                        text(&item.name)
+                            .font(Font {
+                                weight: iced::font::Weight::Bold,
+                                ..Font::DEFAULT
+                            })
+                            .into(),
+                        0.0,
+                    )
+                }
+                outline::OutlineKind::Task(ty) => (
+                    row![
+                        text(ty.to_string())
+                            .font(Font::MONOSPACE)
+                            .style(text::primary),
+                        text(&item.name).font(Font::MONOSPACE),
+                    ]
+                    .spacing(10)
+                    .into(),
+                    if in_section { 20.0 } else { 0.0 },
+                ),
+            };
+            let line = text(format!("{:>4}", item.lines.start + 1))
+                .font(Font::MONOSPACE)
+                .style(text::secondary);
+            let mut entry = row![
+                button(row![line, entry].spacing(10))
+                    .on_press(Message::GotoPosition(item.lines.start, 0))
+                    .padding(Padding::from([2, 5]).left(5.0 + indent))
+                    .width(Fill)
+                    .style(if item.lines.contains(&cur_line) {
+                        button::secondary
+                    } else {
+                        button::text
+                    })
+            ]
+            .spacing(5)
+            .align_y(iced::Alignment::Center);
+            if self.embedded && matches!(item.kind, outline::OutlineKind::Task(_)) {
+                entry = entry.push(icons::action(
+                    icons::run_line_icon(),
+                    "Run Task",
+                    Some(Message::RunLines(item.lines.clone())),
+                ));
+            }
+            list = list.push(entry);
+        }
+        scrollable(list.padding(10)).into()
+    }
+
+    /// Cursor position with the column in chars, iced gives it in
+    /// bytes while the motions and the find offsets count chars
+    fn cursor_chars(&self) -> (usize, usize) {
+        let (line, byte) = self.content.cursor_position();
+        let col = self
+            .content
+            .line(line)
+            .map_or(0, |l| l.get(..byte).unwrap_or(&l).chars().count());
+        (line, col)
+    }
+
+    /// Move the cursor to the line and column (in chars)
+    fn goto(&mut self, line: usize, col: usize) {
+        self.content.perform(text_editor::Action::Move(
+            text_editor::Motion::DocumentStart,
+        ));
+        for _ in 0..line {
+            self.content
+                .perform(text_editor::Action::Move(text_editor::Motion::Down));
+        }
+        // moving right past the end goes to the next line
+        let col = col.min(self.content.line(line).map_or(0, |l| l.chars().count()));
+        for _ in 0..col {
+            self.content
+                .perform(text_editor::Action::Move(text_editor::Motion::Right));
+        }
+    }
+
+    /// Apply the command on the selected lines as a single edit, the
+    /// lines are selected again so the command can be repeated
+    fn line_command(&mut self, cmd: lines::LineCommand) -> Task<Message> {
+        let selected = self.selected_lines();
+        let had_selection = self.content.selection().is_some();
+        let Some(edit) = lines::apply(
+            &self.content.text(),
+            selected.clone(),
+            self.content.cursor_position(),
+            cmd,
+            lines::comment_prefix(self.extension()),
+        ) else {
+            return Task::none();
+        };
+        let old = self.line_texts();
+        self.replace_text(edit.text);
+        let new = edit.lines.clone();
+        match cmd {
+            // the line above the block is moved below it, and the
+            // other way around
+            lines::LineCommand::MoveUp => self.map_markers(|l| {
+                Some(if l == new.start {
+                    new.end
+                } else if l > new.start && l <= new.end {
+                    l - 1
+                } else {
+                    l
+                })
+            }),
+            lines::LineCommand::MoveDown => self.map_markers(|l| {
+                Some(if l + 1 == new.end {
+                    new.start - 1
+                } else if l + 1 >= new.start && l + 1 < new.end {
+                    l + 1
+                } else {
+                    l
+                })
+            }),
+            _ => self.shift_markers(&old),
+        }
+        if had_selection {
+            self.goto(edit.lines.start, 0);
+            self.select_anchor = self.content.cursor_position();
+            for _ in 1..edit.lines.len() {
+                self.content
+                    .perform(text_editor::Action::Select(text_editor::Motion::Down));
+            }
+            self.content
+                .perform(text_editor::Action::Select(text_editor::Motion::End));
+        } else {
+            self.goto(edit.cursor.0, edit.cursor.1);
+        }
+        self.completions.clear();
+        self.changed()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        // watch the file for the changes made by other programs
+        let file = if self.file.is_some() {
+            iced::time::every(Duration::from_secs(1)).map(|_| Message::CheckFile)
+        } else {
+            Subscription::none()
+        };
+        let diagnostics = if self.diagnostics_due.is_some() {
+            iced::time::every(DIAGNOSTICS_DELAY / 2).map(|_| Message::CheckDiagnostics)
+        } else {
+            Subscription::none()
+        };
+        Subscription::batch([file, diagnostics])
+    }
+
+    /// Save the buffer, the file is not overwritten if its
+    /// modification time is not the given one
+    fn save(&mut self, modified: Option<SystemTime>) -> Task<Message> {
+        if self.is_loading {
+            return Task::none();
+        }
+        self.is_loading = true;
+
+        let mut text = self.content.text();
+
+        // // only on 0.14
+        // if let Some(ending) = self.content.line_ending() {
+        //     if !text.ends_with(ending.as_str()) {
+        //         text.push_str(ending.as_str());
+        //     }
+        // }
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+
+        Task::perform(
+            save_file(self.file.clone(), text, modified),
+            Message::FileSaved,
+        )
+    }
+
+    /// Replace the buffer with the file contents, it can be undone
+    fn reload(&mut self, contents: &str) -> Task<Message> {
+        let (line, col) = self.cursor_chars();
+        self.replace_text(contents.to_string());
+        self.goto(line, col);
+        self.mark_saved();
+        self.modified = self.polled;
+        self.conflict = None;
+        self.changed()
+    }
+
+    fn conflict_view(&self) -> Element<'_, Message> {
+        let Some(conflict) = &self.conflict else {
+            let Some(e) = &self.file_error else {
+                return row![].into();
+            };
+            return container(
+                row![
+                    text(e).style(text::danger),
+                    horizontal_space(),
+                    button("Dismiss").on_press(Message::DismissFileError),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            )
+            .padding(5)
+            .style(container::bordered_box)
+            .into();
+        };
+        let msg = if conflict.saving {
+            "The file has changed on disk since it was loaded, saving will overwrite it."
+        } else {
+            "The file has changed on disk, and the buffer has unsaved changes."
+        };
+        let mut buttons = row![
+            text(msg).style(text::danger),
+            horizontal_space(),
+            button("Reload").on_press(Message::ReloadFile),
+            button("Keep Mine").on_press(Message::KeepMine),
+            button(if conflict.diff.is_some() {
+                "Hide Diff"
+            } else {
+                "Diff"
+            })
+            .on_press(Message::ToggleDiff),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+        if conflict.saving {
+            buttons = buttons.push(
+                button("Overwrite")
+                    .on_press(Message::OverwriteFile)
+                    .style(button::danger),
+            );
+        }
+        let mut col = column![buttons].spacing(5);
+        if let Some(diff) = &conflict.diff {
+            col = col.push(
+                scrollable(diff_view(diff))
+                    .height(Length::Shrink)
+                    .width(Fill),
+            );
+        }
+        container(col)
+            .padding(5)
+            .max_height(300)
+            .style(container::bordered_box)
+            .into()
+    }
+
+    /// Replace the whole buffer in a single edit, so it can be undone
+    /// in one step
+    fn replace_text(&mut self, text: String) {
+        self.record(history::EditKind::Other);
+        self.content.perform(text_editor::Action::SelectAll);
+        self.content
+            .perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+                Arc::new(text),
+            )));
+        self.is_dirty = true;
+    }
+
+    /// File type of the buffer if it can be formatted
+    fn formattable(&self) -> Option<my_hl::NadiFileType> {
+        my_hl::NadiFileType::from_str(self.extension())
+            .ok()
+            .filter(|nft| {
+                matches!(
+                    nft,
+                    my_hl::NadiFileType::Tasks | my_hl::NadiFileType::Network
+                )
+            })
+    }
+
+    fn snapshot(&self) -> history::Snapshot {
+        history::Snapshot {
+            text: self.content.text(),
+            cursor: self.cursor_chars(),
+        }
+    }
+
+    /// Save the buffer for undo before an edit that starts a new step
+    fn record(&mut self, kind: history::EditKind) {
+        if self.history.starts_step(kind) {
+            let snap = self.snapshot();
+            self.history.push(snap);
+        }
+    }
+
+    /// Buffer is the same as the file
+    fn mark_saved(&mut self) {
+        self.is_dirty = false;
+        self.saved_text = self.content.text();
+    }
+
+    /// Go back to the buffer from the history
+    fn restore(&mut self, snap: history::Snapshot) -> Task<Message> {
+        self.set_content(&snap.text);
+        self.goto(snap.cursor.0, snap.cursor.1);
+        self.is_dirty = self.content.text() != self.saved_text;
+        self.completions.clear();
+        self.changed()
+    }
+
+    /// Tasks to run after the text changes, the diagnostics are
+    /// checked once the typing pauses
+    fn changed(&mut self) -> Task<Message> {
+        let text = self.content.text();
+        self.cells = cells::cells(&text);
+        if my_hl::NadiFileType::from_str(self.extension()).is_ok() {
+            self.diagnostics_due = Some(Instant::now() + DIAGNOSTICS_DELAY);
+        } else {
+            self.diagnostics_due = None;
+            self.brackets.clear();
+            self.diagnostics.clear();
+        }
+        self.refresh_find(&text);
+        self.follow_scroll();
+        self.func_at_mark(text)
+    }
+
+    /// Parse the buffer for the diagnostics if it hasn't changed for
+    /// a while
+    fn check_diagnostics(&mut self) -> Task<Message> {
+        if self.diagnostics_due.is_none_or(|due| due > Instant::now()) {
+            return Task::none();
+        }
+        self.diagnostics_due = None;
+        match my_hl::NadiFileType::from_str(self.extension()) {
+            Ok(nft) => Task::perform(
+                diagnostics::check(self.content.text(), nft, self.symbols.clone()),
+                Message::Diagnostics,
+            ),
+            Err(_) => Task::none(),
+        }
+    }
+
+    /// Tasks to run after the cursor moves
+    fn moved(&mut self) -> Task<Message> {
+        self.follow_scroll();
+        self.func_at_mark(self.content.text())
+    }
+
+    fn func_at_mark(&self, text: String) -> Task<Message> {
+        Task::perform(
+            task_at_mark(text, self.content.cursor_position()),
+            Message::FuncAtMark,
+        )
+    }
+
+    /// Text from the start of the buffer to the cursor
+    fn text_before_cursor(&self) -> String {
+        let (line, col) = self.content.cursor_position();
+        let mut text = String::new();
+        for l in 0..line {
+            if let Some(l) = self.content.line(l) {
+                text.push_str(&l);
+            }
+            text.push('\n');
+        }
+        // cursor column is in bytes
+        if let Some(l) = self.content.line(line) {
+            text.push_str(l.get(..col).unwrap_or(&l));
+        }
+        text
+    }
+
+    /// Index of the argument being typed for the function at mark,
+    /// from the keyword or the number of commas before the cursor
+    fn active_argument(&self) -> Option<usize> {
+        let (ty, name) = self.function.as_ref()?;
+        let args = self.symbols.args(ty, name)?;
+        let prefix = self.text_before_cursor();
+        let (call, arg) = complete::call_argument(&prefix)?;
+        if call != name {
+            return None;
+        }
+        match arg {
+            complete::CallArg::Keyword(k) => args
+                .iter()
+                .position(|a| a.name == k && a.variadic.is_empty())
+                .or_else(|| args.iter().position(|a| a.variadic == "**")),
+            complete::CallArg::Position(n) => args
+                .iter()
+                .enumerate()
+                .filter(|(_, a)| a.variadic != "**")
+                .nth(n)
+                .or_else(|| args.iter().enumerate().find(|(_, a)| a.variadic == "*"))
+                .map(|(i, _)| i),
+        }
+    }
+
+    fn signature_view(&self) -> Element<'_, Message> {
+        let Some((ty, name)) = &self.function else {
+            return row![].into();
+        };
+        let Some(args) = self.symbols.args(ty, name) else {
+            return text(format!("{ty} {name}")).into();
+        };
+        let theme = self.theme();
+        let color = |h: my_hl::Highlight| h.format_with(Some(self.theme), &theme).color;
+        let bold = Font {
+            weight: iced::font::Weight::Bold,
+            ..Font::MONOSPACE
+        };
+        let mut spans = vec![
+            span(format!("{ty} ")).color_maybe(color(my_hl::Highlight::Keyword)),
+            span(name.clone()).color_maybe(color(my_hl::Highlight::Function)),
+            span("("),
+        ];
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                spans.push(span(", "));
+            }
+            let mut sig = format!("{}{}", arg.variadic, arg.name);
+            if arg.variadic.is_empty() {
+                sig.push_str(&format!(": {}", arg.ty));
+                if let Some(def) = &arg.default {
+                    sig.push_str(&format!(" = {def}"));
+                }
+            }
+            spans.push(if self.active_arg == Some(i) {
+                span(sig)
+                    .font(bold)
+                    .underline(true)
+                    .color_maybe(color(my_hl::Highlight::Variable))
+            } else {
+                span(sig)
+            });
+        }
+        spans.push(span(")"));
+        let mut col = column![rich_text(spans).font(Font::MONOSPACE)];
+        if let Some(arg) = self.active_arg.and_then(|i| args.get(i)) {
+            col = col.push(
+                text(format!("{}: {}", arg.name, arg.help.trim()))
+                    .size(12)
+                    .style(text::secondary),
+            );
+        }
+        col.into()
+    }
+
+    fn extension(&self) -> &str {
+        self.file
+            .as_deref()
+            .and_then(Path::extension)
+            .and_then(std::ffi::OsStr::to_str)
+            .unwrap_or("tasks")
+    }
+
+    fn is_tasks(&self) -> bool {
+        matches!(
+            my_hl::NadiFileType::from_str(self.extension()),
+            Ok(my_hl::NadiFileType::Tasks)
+        )
+    }
+
+    /// Update the completions for the word at the cursor
+    fn complete(&mut self) {
+        self.completion_sel = 0;
+        if !self.is_tasks() {
+            self.completions.clear();
+            return;
+        }
+        let (line, col) = self.content.cursor_position();
+        let prefix: String = self
+            .content
+            .line(line)
+            .map(|l| l.get(..col).unwrap_or(&l).to_string())
+            .unwrap_or_default();
+        (self.completion_len, self.completions) = self.symbols.complete(&prefix);
+    }
+
+    fn completion_view(&self) -> Element<'_, Message> {
+        // show a window of candidates around the selected one
+        let start = self.completion_sel.saturating_sub(4);
+        let mut list = Column::new().width(350);
+        for (i, comp) in self.completions.iter().enumerate().skip(start).take(8) {
+            list = list.push(
+                button(row![
+                    text(comp.label.clone()).font(Font::MONOSPACE),
+                    horizontal_space(),
+                    text(comp.detail.clone()).size(12).style(text::secondary)
+                ])
+                .on_press(Message::AcceptCompletion(i))
+                .width(Fill)
+                .padding([2, 5])
+                .style(if i == self.completion_sel {
+                    button::primary
+                } else {
+                    button::text
+                }),
+            );
+        }
+        container(list)
+            .padding(5)
+            .style(container::bordered_box)
             .into()
     }
 
@@ -248,10 +1914,53 @@ impl Editor {
     }
 }
 
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Error => Color::from_rgb(1.0, 0.3, 0.3),
+        Severity::Warning => Color::from_rgb(0.9, 0.6, 0.0),
+    }
+}
+
+/// Changes in the file on disk compared to the buffer, only the
+/// lines near the changes are shown
+fn diff_view(diff: &[diff::DiffLine]) -> Element<'_, Message> {
+    let changed = |i: usize| {
+        diff.get(i)
+            .is_some_and(|d| !matches!(d, diff::DiffLine::Same(_)))
+    };
+    let mut list = Column::new();
+    let mut skipped = false;
+    for (i, line) in diff.iter().enumerate() {
+        let line = match line {
+            diff::DiffLine::Removed(l) => text(format!("- {l}")).style(text::danger),
+            diff::DiffLine::Added(l) => text(format!("+ {l}")).style(text::success),
+            diff::DiffLine::Same(l) => {
+                if !(i.saturating_sub(2)..=(i + 2)).any(changed) {
+                    if !skipped {
+                        list = list.push(text("  ...").style(text::secondary));
+                    }
+                    skipped = true;
+                    continue;
+                }
+                text(format!("  {l}")).style(text::secondary)
+            }
+        };
+        skipped = false;
+        list = list.push(line.font(Font::MONOSPACE).size(14));
+    }
+    list.into()
+}
+
+fn file_modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
 #[derive(Debug, Clone)]
 pub enum Error {
     DialogClosed,
     IoError(std::io::ErrorKind),
+    /// file was modified by another program, with its contents
+    Modified(Arc<String>),
 }
 
 async fn open_file() -> Result<(PathBuf, Arc<String>), Error> {
@@ -279,8 +1988,20 @@ async fn load_file(path: impl Into<PathBuf>) -> Result<(PathBuf, Arc<String>), E
     Ok((path, contents))
 }
 
-async fn save_file(path: Option<PathBuf>, contents: String) -> Result<PathBuf, Error> {
+async fn save_file(
+    path: Option<PathBuf>,
+    contents: String,
+    modified: Option<SystemTime>,
+) -> Result<PathBuf, Error> {
     let path = if let Some(path) = path {
+        let current = tokio::fs::metadata(&path)
+            .await
+            .and_then(|m| m.modified())
+            .ok();
+        if modified.is_some() && current.is_some() && current != modified {
+            let (_, disk) = load_file(&path).await?;
+            return Err(Error::Modified(disk));
+        }
         path
     } else {
         rfd::AsyncFileDialog::new()
@@ -0,0 +1,185 @@
+//! Matching brackets in the nadi files, the brackets inside the
+//! strings and comments are not counted.
+use super::diagnostics::Diagnostic;
+use nadi_core::parser::tokenizer::{TaskToken, Token};
+use nadi_core::tasks::TaskKeyword;
+
+#[derive(Clone, Debug)]
+pub struct Bracket {
+    pub line: usize,
+    /// char column in the line
+    pub col: usize,
+    pub ch: char,
+    /// index of the matching bracket, None if it is unmatched
+    pub pair: Option<usize>,
+}
+
+/// Brackets in the tokens, `<` and `>` are only brackets after the
+/// `node` and `network` keywords (`node<a -> b>`), elsewhere they
+/// are comparisons
+pub fn brackets(tokens: &[Token]) -> Vec<Bracket> {
+    let mut brackets: Vec<Bracket> = vec![];
+    // open brackets that are not closed yet
+    let mut open: Vec<usize> = vec![];
+    let (mut line, mut col) = (0, 0);
+    let mut prev: Option<&TaskToken> = None;
+    for token in tokens {
+        let opening = match &token.ty {
+            TaskToken::ParenStart | TaskToken::BraceStart | TaskToken::BracketStart => Some(true),
+            TaskToken::AngleStart => prev
+                .is_some_and(|p| {
+                    matches!(
+                        p,
+                        TaskToken::Keyword(TaskKeyword::Node | TaskKeyword::Network)
+                    )
+                })
+                .then_some(true),
+            TaskToken::ParenEnd | TaskToken::BraceEnd | TaskToken::BracketEnd => Some(false),
+            TaskToken::AngleEnd => open
+                .last()
+                .is_some_and(|&o| brackets[o].ch == '<')
+                .then_some(false),
+            _ => None,
+        };
+        if let Some(opening) = opening {
+            let ch = token.content.chars().next().unwrap_or_default();
+            let ind = brackets.len();
+            let mut pair = None;
+            if opening {
+                open.push(ind);
+            } else if let Some(&o) = open.last()
+                && closing(brackets[o].ch) == ch
+            {
+                open.pop();
+                brackets[o].pair = Some(ind);
+                pair = Some(o);
+            }
+            brackets.push(Bracket {
+                line,
+                col,
+                ch,
+                pair,
+            });
+        }
+        if !matches!(token.ty, TaskToken::WhiteSpace) {
+            prev = Some(&token.ty);
+        }
+        match token.content.rsplit_once('\n') {
+            Some((before, after)) => {
+                line += before.matches('\n').count() + 1;
+                col = after.chars().count();
+            }
+            None => col += token.content.chars().count(),
+        }
+    }
+    brackets
+}
+
+fn closing(ch: char) -> char {
+    match ch {
+        '(' => ')',
+        '{' => '}',
+        '[' => ']',
+        '<' => '>',
+        _ => ch,
+    }
+}
+
+/// Bracket at the cursor (before or after it) and its match
+pub fn matching(brackets: &[Bracket], (line, col): (usize, usize)) -> Option<[&Bracket; 2]> {
+    let at = |c: usize| {
+        brackets
+            .iter()
+            .find(|b| b.line == line && b.col == c && b.pair.is_some())
+    };
+    let bracket = at(col).or_else(|| col.checked_sub(1).and_then(at))?;
+    Some([bracket, &brackets[bracket.pair?]])
+}
+
+/// Errors for the brackets without a match
+pub fn unmatched(brackets: &[Bracket]) -> Vec<Diagnostic> {
+    brackets
+        .iter()
+        .filter(|b| b.pair.is_none())
+        .map(|b| Diagnostic::error(b.line, b.col..(b.col + 1), format!("Unmatched `{}`", b.ch)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nadi_core::parser::tokenizer::get_tokens;
+
+    fn find(text: &str) -> Vec<Bracket> {
+        brackets(&get_tokens(text).unwrap())
+    }
+
+    /// Positions of the brackets with the ones they pair with
+    fn pairs(text: &str) -> Vec<(char, (usize, usize), Option<usize>)> {
+        find(text)
+            .into_iter()
+            .map(|b| (b.ch, (b.line, b.col), b.pair))
+            .collect()
+    }
+
+    #[test]
+    fn nested() {
+        assert_eq!(
+            pairs("node.f(a, [1, 2])"),
+            [
+                ('(', (0, 6), Some(3)),
+                ('[', (0, 10), Some(2)),
+                (']', (0, 15), Some(1)),
+                (')', (0, 16), Some(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn columns_in_chars() {
+        // the bracket in the string is not counted
+        assert_eq!(
+            pairs(r#"env.f("é(", {a = 1})"#),
+            [
+                ('(', (0, 5), Some(3)),
+                ('{', (0, 12), Some(2)),
+                ('}', (0, 18), Some(1)),
+                (')', (0, 19), Some(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn multiple_lines() {
+        assert_eq!(
+            pairs("env.f(\n  \"é\",\n)"),
+            [('(', (0, 5), Some(1)), (')', (2, 0), Some(0))]
+        );
+    }
+
+    #[test]
+    fn angle_brackets() {
+        let b = pairs("node<a -> b> f()");
+        assert_eq!(b[0], ('<', (0, 4), Some(1)));
+        assert_eq!(b[1], ('>', (0, 11), Some(0)));
+        assert_eq!(b.len(), 4);
+    }
+
+    #[test]
+    fn unmatched_brackets() {
+        let b = find("env.f(]");
+        assert!(b.iter().all(|b| b.pair.is_none()));
+        assert_eq!(unmatched(&b).len(), 2);
+        assert!(unmatched(&find("env.f()")).is_empty());
+    }
+
+    #[test]
+    fn matching_at_cursor() {
+        let b = find(r#"env.f("é", [1])"#);
+        let pos = |m: Option<[&Bracket; 2]>| m.map(|[a, b]| ((a.line, a.col), (b.line, b.col)));
+        // before and after the bracket
+        assert_eq!(pos(matching(&b, (0, 11))), Some(((0, 11), (0, 13))));
+        assert_eq!(pos(matching(&b, (0, 15))), Some(((0, 14), (0, 5))));
+        assert_eq!(pos(matching(&b, (0, 8))), None);
+    }
+}
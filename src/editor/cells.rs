@@ -0,0 +1,81 @@
+//! Cells of the buffer, separated by the `# %%` marker lines, that
+//! can be run one at a time.
+use std::ops::Range;
+
+static MARKER: &str = "# %%";
+
+pub fn is_marker(line: &str) -> bool {
+    line.trim_start().starts_with(MARKER)
+}
+
+/// Line ranges of the cells, each cell starts at its marker and the
+/// lines before the first marker are a cell of their own
+pub fn cells(text: &str) -> Vec<Range<usize>> {
+    let mut cells = vec![];
+    let mut start = 0;
+    let mut count = 0;
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 && is_marker(line) {
+            cells.push(start..i);
+            start = i;
+        }
+        count = i + 1;
+    }
+    cells.push(start..count);
+    cells
+}
+
+/// Cell containing the line
+pub fn cell_at(cells: &[Range<usize>], line: usize) -> Range<usize> {
+    cells
+        .iter()
+        .find(|c| c.contains(&line))
+        .cloned()
+        .unwrap_or(line..(line + 1))
+}
+
+/// Whether the line has something to run
+pub fn is_code(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with('#')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markers() {
+        assert!(is_marker("# %%"));
+        assert!(is_marker("  # %% load the network"));
+        assert!(!is_marker("#%%"));
+        assert!(!is_marker("x # %%"));
+    }
+
+    #[test]
+    fn cell_ranges() {
+        assert_eq!(cells(""), vec![(0..1)]);
+        assert_eq!(cells("a\nb"), vec![(0..2)]);
+        let text = "a\n# %% one\nb\nc\n# %% two\nd\n";
+        assert_eq!(cells(text), [0..1, 1..4, 4..7]);
+        // first line is the marker of the first cell
+        assert_eq!(cells("# %%\na\n# %%"), [0..2, 2..3]);
+    }
+
+    #[test]
+    fn cell_at_line() {
+        let c = cells("a\n# %%\nb\nc");
+        assert_eq!(cell_at(&c, 0), 0..1);
+        assert_eq!(cell_at(&c, 2), 1..4);
+        // outside of the cells
+        assert_eq!(cell_at(&c, 9), 9..10);
+    }
+
+    #[test]
+    fn code_lines() {
+        assert!(is_code("  env.x = 1"));
+        assert!(!is_code("   "));
+        assert!(!is_code("# comment"));
+        assert!(!is_code("# %%"));
+    }
+}
@@ -0,0 +1,454 @@
+//! Completion candidates for the tasks files.
+use super::my_hl::Names;
+use super::template;
+use crate::help::FuncType;
+use crate::help::page::ArgHelp;
+use abi_stable::std_types::{RHashMap, RString, Tuple2};
+use nadi_core::attrs::HasAttributes;
+use nadi_core::functions::NadiFunctions;
+use nadi_core::tasks::TaskContext;
+use std::sync::Arc;
+
+/// Maximum number of candidates to show
+static MAX_COMPLETIONS: usize = 50;
+
+#[derive(Clone, Debug)]
+pub struct Completion {
+    pub label: String,
+    /// text replacing the word at the cursor
+    pub insert: String,
+    pub detail: String,
+}
+
+#[derive(Clone, Debug, Default)]
+struct FuncSymbol {
+    name: String,
+    args: Vec<ArgHelp>,
+}
+
+/// Argument of the function call being typed
+#[derive(Debug, PartialEq)]
+pub enum CallArg {
+    Position(usize),
+    Keyword(String),
+}
+
+/// Names available for completion, the functions are loaded once,
+/// while the attributes and nodes are updated from the network as
+/// the tasks are run
+#[derive(Clone, Default)]
+pub struct Symbols {
+    env: Vec<FuncSymbol>,
+    node: Vec<FuncSymbol>,
+    network: Vec<FuncSymbol>,
+    env_vars: Vec<String>,
+    node_attrs: Vec<String>,
+    network_attrs: Vec<String>,
+    nodes: Vec<String>,
+    /// all the names for the highlighter, updated with the symbols
+    names: Option<Arc<Names>>,
+}
+
+/// What is being typed at the cursor
+#[derive(Debug, PartialEq)]
+enum Context {
+    Keyword,
+    Function(FuncType),
+    Argument(FuncType, String),
+    NodeAttr,
+    NetworkAttr,
+    EnvVar,
+    NodeName,
+}
+
+fn func_symbols<F>(
+    funcs: &RHashMap<RString, F>,
+    alias: &RHashMap<RString, RString>,
+    args: impl Fn(&F) -> Vec<ArgHelp>,
+) -> Vec<FuncSymbol> {
+    let mut symbols: Vec<FuncSymbol> = funcs
+        .iter()
+        .map(|Tuple2(name, f)| FuncSymbol {
+            name: name.to_string(),
+            args: args(f),
+        })
+        .collect();
+    // short names without the plugin
+    for Tuple2(short, full) in alias.iter() {
+        if let Some(f) = funcs.get(full) {
+            symbols.push(FuncSymbol {
+                name: short.to_string(),
+                args: args(f),
+            });
+        }
+    }
+    symbols.sort_by(|a, b| a.name.cmp(&b.name));
+    symbols
+}
+
+impl Symbols {
+    pub fn new(ctx: &TaskContext) -> Self {
+        let mut sym = Self::default();
+        sym.load_functions(&ctx.functions);
+        sym.load_context(ctx);
+        sym
+    }
+
+    pub fn load_functions(&mut self, functions: &NadiFunctions) {
+        self.env = func_symbols(functions.env_functions(), functions.env_alias(), |f| {
+            f.args().iter().map(ArgHelp::from).collect()
+        });
+        self.node = func_symbols(functions.node_functions(), functions.node_alias(), |f| {
+            f.args().iter().map(ArgHelp::from).collect()
+        });
+        self.network = func_symbols(
+            functions.network_functions(),
+            functions.network_alias(),
+            |f| f.args().iter().map(ArgHelp::from).collect(),
+        );
+        self.update_names();
+    }
+
+    /// Update the attributes and node names from the network
+    pub fn load_context(&mut self, ctx: &TaskContext) {
+        let sorted = |mut v: Vec<String>| {
+            v.sort();
+            v.dedup();
+            v
+        };
+        self.env_vars = sorted(ctx.env.keys().map(|k| k.to_string()).collect());
+        self.network_attrs = sorted(
+            ctx.network
+                .attr_map()
+                .keys()
+                .map(|k| k.to_string())
+                .collect(),
+        );
+        self.node_attrs = sorted(
+            ctx.network
+                .nodes()
+                .flat_map(|n| {
+                    n.lock()
+                        .attr_map()
+                        .keys()
+                        .map(|k| k.to_string())
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+        );
+        self.nodes = ctx.network.node_names().map(String::from).collect();
+        self.update_names();
+    }
+
+    fn update_names(&mut self) {
+        // without the functions everything would be unknown
+        if self.env.is_empty() && self.node.is_empty() && self.network.is_empty() {
+            self.names = None;
+            return;
+        }
+        let names = |funcs: &[FuncSymbol]| funcs.iter().map(|f| f.name.clone()).collect();
+        let new = Names::new(
+            [names(&self.env), names(&self.node), names(&self.network)],
+            [
+                self.env_vars.clone(),
+                self.node_attrs.clone(),
+                self.network_attrs.clone(),
+            ],
+            [&self.env, &self.node, &self.network]
+                .into_iter()
+                .flatten()
+                .filter(|f| f.args.iter().any(|a| template::is_template(&a.ty)))
+                .map(|f| f.name.clone())
+                .collect(),
+        );
+        // a new generation makes the editor highlight everything again
+        if self.names.as_ref().is_some_and(|n| n.same_names(&new)) {
+            return;
+        }
+        self.names = Some(Arc::new(new));
+    }
+
+    /// Known functions and attributes, None if the functions are not
+    /// loaded
+    pub fn names(&self) -> Option<Arc<Names>> {
+        self.names.clone()
+    }
+
+    fn functions(&self, ty: &FuncType) -> &[FuncSymbol] {
+        match ty {
+            FuncType::Env => &self.env,
+            FuncType::Node => &self.node,
+            FuncType::Network => &self.network,
+        }
+    }
+
+    /// Arguments of the function, None if it is not found
+    pub fn args(&self, ty: &FuncType, name: &str) -> Option<&[ArgHelp]> {
+        self.functions(ty)
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| f.args.as_slice())
+    }
+
+    /// Completions for the line typed up to the cursor, along with the
+    /// number of characters before the cursor they replace
+    pub fn complete(&self, prefix: &str) -> (usize, Vec<Completion>) {
+        let Some((word, ctx)) = context(prefix) else {
+            return (0, vec![]);
+        };
+        let names = |names: &[String], detail: &str| -> Vec<Completion> {
+            names
+                .iter()
+                .map(|n| Completion {
+                    label: n.clone(),
+                    insert: n.clone(),
+                    detail: detail.to_string(),
+                })
+                .collect()
+        };
+        let candidates = match &ctx {
+            Context::Keyword => ["node", "network", "env", "help", "exit"]
+                .iter()
+                .map(|k| Completion {
+                    label: k.to_string(),
+                    insert: k.to_string(),
+                    detail: "keyword".into(),
+                })
+                .collect(),
+            Context::Function(ty) => self
+                .functions(ty)
+                .iter()
+                .map(|f| Completion {
+                    label: f.name.clone(),
+                    insert: f.name.clone(),
+                    detail: format!("{ty} function"),
+                })
+                .collect(),
+            Context::Argument(ty, func) => self
+                .args(ty, func)
+                .unwrap_or_default()
+                .iter()
+                .filter(|a| a.variadic.is_empty())
+                .map(|a| Completion {
+                    label: a.name.clone(),
+                    insert: format!("{} = ", a.name),
+                    detail: a.ty.clone(),
+                })
+                .collect(),
+            Context::NodeAttr => names(&self.node_attrs, "node attribute"),
+            Context::NetworkAttr => names(&self.network_attrs, "network attribute"),
+            Context::EnvVar => names(&self.env_vars, "env variable"),
+            Context::NodeName => names(&self.nodes, "node"),
+        };
+        // nothing to complete if the word is already complete
+        let candidates: Vec<Completion> = candidates
+            .into_iter()
+            .filter(|c| c.insert != word)
+            .collect();
+        let mut matches: Vec<Completion> = candidates
+            .iter()
+            .filter(|c| c.label.starts_with(word))
+            .cloned()
+            .collect();
+        // then the ones that have the word somewhere else
+        matches.extend(
+            candidates
+                .into_iter()
+                .filter(|c| !c.label.starts_with(word) && c.label.contains(word)),
+        );
+        matches.truncate(MAX_COMPLETIONS);
+        (word.chars().count(), matches)
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Keyword at the end of the text, skipping the propagation
+/// (e.g. `node[A -> B]` or `network(area)`) after it
+fn keyword_before(text: &str) -> Option<FuncType> {
+    let mut text = text.trim_end();
+    if let Some(close) = text.chars().last().filter(|c| matches!(c, ')' | ']')) {
+        let open = if close == ')' { '(' } else { '[' };
+        let mut depth = 0;
+        let mut start = None;
+        for (i, c) in text.char_indices().rev() {
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                depth -= 1;
+                if depth == 0 {
+                    start = Some(i);
+                    break;
+                }
+            }
+        }
+        text = &text[..start?];
+    }
+    keyword_at_end(text)
+}
+
+/// Start of the identifier at the end of the text, optionally
+/// including the dots for the function names with plugin
+fn ident_start(text: &str, dots: bool) -> Option<usize> {
+    text.char_indices()
+        .rev()
+        .take_while(|(_, c)| is_ident(*c) || (dots && *c == '.'))
+        .last()
+        .map(|(i, _)| i)
+}
+
+/// Keyword immediately at the end of the text
+fn keyword_at_end(text: &str) -> Option<FuncType> {
+    let start = ident_start(text, false)?;
+    match &text[start..] {
+        "node" => Some(FuncType::Node),
+        "network" => Some(FuncType::Network),
+        "env" => Some(FuncType::Env),
+        _ => None,
+    }
+}
+
+/// The word being typed and what it could be, None if there is
+/// nothing to complete (e.g. inside strings and comments)
+fn context(prefix: &str) -> Option<(&str, Context)> {
+    let mut word_start = ident_start(prefix, false).unwrap_or(prefix.len());
+    // function names can have the plugin name: `plugin.function`
+    let mut plugin = false;
+    if let Some(before) = prefix[..word_start].strip_suffix('.')
+        && keyword_before(before).is_none()
+        && let Some(s) = ident_start(before, false)
+    {
+        word_start = s;
+        plugin = true;
+    }
+    let word = &prefix[word_start..];
+    let before = &prefix[..word_start];
+
+    // unclosed brackets before the word
+    let mut brackets = vec![];
+    let mut in_str = false;
+    let mut escaped = false;
+    for (i, c) in before.char_indices() {
+        if in_str {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_str = false,
+                _ => (),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_str = true,
+            '#' => return None,
+            '(' | '[' | '{' => brackets.push((c, i)),
+            ')' | ']' | '}' => {
+                brackets.pop();
+            }
+            _ => (),
+        }
+    }
+    if in_str {
+        return None;
+    }
+
+    if !plugin && let Some(head) = before.strip_suffix('.') {
+        return match keyword_before(head)? {
+            FuncType::Node => Some((word, Context::NodeAttr)),
+            FuncType::Network => Some((word, Context::NetworkAttr)),
+            FuncType::Env => Some((word, Context::EnvVar)),
+        };
+    }
+
+    if let Some((open, pos)) = brackets.last() {
+        let head = &before[..*pos];
+        return match open {
+            // propositions like node(INDEX > 0)
+            '(' if keyword_at_end(head).is_some() => Some((word, Context::NodeAttr)),
+            '[' if keyword_at_end(head).is_some() => Some((word, Context::NodeName)),
+            '(' => {
+                // argument names only at the start of an argument
+                let arg_start = before.trim_end();
+                if !(arg_start.ends_with('(') || arg_start.ends_with(',')) {
+                    return None;
+                }
+                let fstart = ident_start(head, true)?;
+                let ty = keyword_before(&head[..fstart])?;
+                Some((word, Context::Argument(ty, head[fstart..].to_string())))
+            }
+            _ => None,
+        };
+    }
+
+    if before.trim().is_empty() {
+        return Some((word, Context::Keyword));
+    }
+    // function after the keyword and optional propagation, with
+    // space between them
+    if before.ends_with(char::is_whitespace)
+        && let Some(ty) = keyword_before(before)
+    {
+        return Some((word, Context::Function(ty)));
+    }
+    None
+}
+
+/// Function name and the argument being typed, if the text ends
+/// inside a function call
+pub fn call_argument(prefix: &str) -> Option<(&str, CallArg)> {
+    // unclosed brackets, with the number of commas directly inside
+    // them and the position of the last one
+    let mut brackets: Vec<(char, usize, usize, Option<usize>)> = vec![];
+    let mut in_str = false;
+    let mut escaped = false;
+    let mut comment = false;
+    for (i, c) in prefix.char_indices() {
+        if comment {
+            comment = c != '\n';
+            continue;
+        }
+        if in_str {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_str = false,
+                _ => (),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_str = true,
+            '#' => comment = true,
+            '(' | '[' | '{' => brackets.push((c, i, 0, None)),
+            ')' | ']' | '}' => {
+                brackets.pop();
+            }
+            ',' => {
+                if let Some(b) = brackets.last_mut() {
+                    b.2 += 1;
+                    b.3 = Some(i);
+                }
+            }
+            _ => (),
+        }
+    }
+    if comment {
+        return None;
+    }
+    let (open, pos, commas, last_comma) = brackets.pop()?;
+    if open != '(' {
+        return None;
+    }
+    let head = &prefix[..pos];
+    let name = &head[ident_start(head, true)?..];
+    let arg = prefix[last_comma.unwrap_or(pos) + 1..].trim_start();
+    let id_end = arg.find(|c| !is_ident(c)).unwrap_or(arg.len());
+    let rest = arg[id_end..].trim_start();
+    if id_end > 0 && rest.starts_with('=') && !rest.starts_with("==") {
+        Some((name, CallArg::Keyword(arg[..id_end].to_string())))
+    } else {
+        Some((name, CallArg::Position(commas)))
+    }
+}
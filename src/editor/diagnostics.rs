@@ -0,0 +1,139 @@
+//! Errors and warnings in the buffer shown in the editor.
+use super::complete::Symbols;
+use super::my_hl::NadiFileType;
+use super::outline::{self, OutlineItem};
+use super::{brackets, lint};
+use nadi_core::parser::{self, tokenizer};
+use std::ops::Range;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub line: usize,
+    /// char range of the problem in the line
+    pub cols: Range<usize>,
+    pub message: String,
+    pub severity: Severity,
+}
+
+impl Diagnostic {
+    pub fn error(line: usize, cols: Range<usize>, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            cols,
+            message: message.into(),
+            severity: Severity::Error,
+        }
+    }
+
+    pub fn warning(line: usize, cols: Range<usize>, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            cols,
+            message: message.into(),
+            severity: Severity::Warning,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!("{}:{} {}", self.line + 1, self.cols.start + 1, self.message)
+    }
+
+    /// Byte range of the problem in the line text
+    pub fn byte_range(&self, line: &str) -> Range<usize> {
+        let byte = |c: usize| {
+            line.char_indices()
+                .nth(c)
+                .map(|(i, _)| i)
+                .unwrap_or(line.len())
+        };
+        byte(self.cols.start)..byte(self.cols.end)
+    }
+}
+
+/// Char range of the token starting at the byte offset in the line,
+/// at least one char long
+fn token_span(line: &str, offset: usize) -> Range<usize> {
+    let offset = offset.min(line.len());
+    let start = line.char_indices().take_while(|(i, _)| *i < offset).count();
+    let len = tokenizer::get_tokens(&line[offset..])
+        .ok()
+        .and_then(|t| t.first().map(|t| t.content.trim_end().chars().count()))
+        .unwrap_or_else(|| {
+            line[offset..]
+                .chars()
+                .take_while(|c| !c.is_whitespace())
+                .count()
+        });
+    start..(start + len.max(1))
+}
+
+/// Problems in the buffer, and the outline of the tasks
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    pub diagnostics: Vec<Diagnostic>,
+    /// None if the buffer is not a tasks file that can be parsed
+    pub outline: Option<Vec<OutlineItem>>,
+    pub brackets: Vec<brackets::Bracket>,
+}
+
+/// Check the buffer away from the UI, the functions in the tasks are
+/// only checked when they can be parsed
+pub async fn check(text: String, nft: NadiFileType, symbols: Arc<Symbols>) -> Report {
+    let (diagnostics, brackets) = parse_errors(&text, &nft);
+    if !diagnostics.is_empty() || nft != NadiFileType::Tasks {
+        return Report {
+            diagnostics,
+            outline: None,
+            brackets,
+        };
+    }
+    Report {
+        diagnostics: lint::check(&text, &symbols),
+        outline: outline::outline(&text),
+        brackets,
+    }
+}
+
+/// Parse the buffer and return the error if there is any, along with
+/// the brackets in it
+fn parse_errors(text: &str, nft: &NadiFileType) -> (Vec<Diagnostic>, Vec<brackets::Bracket>) {
+    let tokens = match tokenizer::get_tokens(text) {
+        Ok(t) => t,
+        Err(e) => {
+            // tokenizer column starts from 1
+            let cols = token_span(&e.linestr, e.col.saturating_sub(1));
+            return (
+                vec![Diagnostic::error(e.line, cols, "Invalid token")],
+                vec![],
+            );
+        }
+    };
+    let brackets = brackets::brackets(&tokens);
+    // the parser errors are less clear about the missing brackets
+    let unmatched = brackets::unmatched(&brackets);
+    if !unmatched.is_empty() {
+        return (unmatched, brackets);
+    }
+    let res = match nft {
+        NadiFileType::Tasks => parser::tasks::parse(tokens).map(|_| ()),
+        NadiFileType::Network => parser::network::parse(tokens).map(|_| ()),
+        NadiFileType::Attribute => parser::attrs::parse(tokens).map(|_| ()),
+        NadiFileType::Terminal => Ok(()),
+    };
+    let diagnostics = match res {
+        Ok(_) => vec![],
+        Err(e) => vec![Diagnostic::error(
+            e.line,
+            token_span(&e.linestr, e.col),
+            e.ty.message(),
+        )],
+    };
+    (diagnostics, brackets)
+}
@@ -0,0 +1,146 @@
+//! Line diff between the buffer and the file on disk.
+
+/// Cells in the table of the common subsequences above which the
+/// changed lines are listed as removed and then added, so a large
+/// change doesn't take a lot of memory
+static MAX_TABLE: usize = 4_000_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffLine {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// Lines removed from the old text and added in the new one, from
+/// the longest common subsequence of the lines
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    // the common start and end don't need to be in the table
+    let pre = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suf = a[pre..]
+        .iter()
+        .rev()
+        .zip(b[pre..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let am = &a[pre..(a.len() - suf)];
+    let bm = &b[pre..(b.len() - suf)];
+
+    let mut diff: Vec<DiffLine> = a[..pre]
+        .iter()
+        .map(|l| DiffLine::Same(l.to_string()))
+        .collect();
+    if (am.len() + 1) * (bm.len() + 1) > MAX_TABLE {
+        diff.extend(am.iter().map(|l| DiffLine::Removed(l.to_string())));
+        diff.extend(bm.iter().map(|l| DiffLine::Added(l.to_string())));
+    } else {
+        diff.extend(lcs_diff(am, bm));
+    }
+    diff.extend(
+        a[(a.len() - suf)..]
+            .iter()
+            .map(|l| DiffLine::Same(l.to_string())),
+    );
+    diff
+}
+
+/// Diff of the lines from the table of the longest common
+/// subsequences
+fn lcs_diff(am: &[&str], bm: &[&str]) -> Vec<DiffLine> {
+    // lcs[i][j] is the length of the common subsequence of am[i..]
+    // and bm[j..]
+    let mut lcs = vec![vec![0u32; bm.len() + 1]; am.len() + 1];
+    for i in (0..am.len()).rev() {
+        for j in (0..bm.len()).rev() {
+            lcs[i][j] = if am[i] == bm[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < am.len() || j < bm.len() {
+        if i < am.len() && j < bm.len() && am[i] == bm[j] {
+            diff.push(DiffLine::Same(am[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if j < bm.len() && (i == am.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            diff.push(DiffLine::Added(bm[j].to_string()));
+            j += 1;
+        } else {
+            diff.push(DiffLine::Removed(am[i].to_string()));
+            i += 1;
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DiffLine::*;
+
+    fn same(l: &str) -> DiffLine {
+        Same(l.to_string())
+    }
+
+    fn removed(l: &str) -> DiffLine {
+        Removed(l.to_string())
+    }
+
+    fn added(l: &str) -> DiffLine {
+        Added(l.to_string())
+    }
+
+    #[test]
+    fn no_changes() {
+        assert_eq!(line_diff("a\nb", "a\nb"), [same("a"), same("b")]);
+        assert!(line_diff("", "").is_empty());
+    }
+
+    #[test]
+    fn changed_lines() {
+        assert_eq!(
+            line_diff("a\né\nc\nd", "a\nü\nc\nd\ne"),
+            [
+                same("a"),
+                added("ü"),
+                removed("é"),
+                same("c"),
+                same("d"),
+                added("e")
+            ]
+        );
+        assert_eq!(
+            line_diff("x\na\nb", "a\nb\ny"),
+            [removed("x"), same("a"), same("b"), added("y")]
+        );
+    }
+
+    #[test]
+    fn moved_line() {
+        assert_eq!(
+            line_diff("a\nb\nc", "b\nc\na"),
+            [removed("a"), same("b"), same("c"), added("a")]
+        );
+    }
+
+    #[test]
+    fn large_change() {
+        let old: Vec<String> = (0..3000).map(|i| format!("a{i}")).collect();
+        let new: Vec<String> = (0..3000).map(|i| format!("b{i}")).collect();
+        let old = format!("start\n{}\nend", old.join("\n"));
+        let new = format!("start\n{}\nend", new.join("\n"));
+        let d = line_diff(&old, &new);
+        assert_eq!(d.len(), 6002);
+        assert_eq!(d[0], same("start"));
+        assert_eq!(d[1], removed("a0"));
+        assert_eq!(d[3001], added("b0"));
+        assert_eq!(d[6001], same("end"));
+    }
+}
@@ -0,0 +1,265 @@
+//! Find and replace in the editor buffer.
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FindOption {
+    CaseSensitive,
+    WholeWord,
+    Regex,
+}
+
+#[derive(Default)]
+pub struct Find {
+    pub query: String,
+    pub replacement: String,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub regex: bool,
+    /// byte ranges of the matches in the buffer
+    pub matches: Vec<Range<usize>>,
+    pub current: usize,
+    /// error in the regex pattern
+    pub error: Option<String>,
+}
+
+impl Find {
+    pub fn toggle(&mut self, opt: FindOption) {
+        match opt {
+            FindOption::CaseSensitive => self.case_sensitive = !self.case_sensitive,
+            FindOption::WholeWord => self.whole_word = !self.whole_word,
+            FindOption::Regex => self.regex = !self.regex,
+        }
+    }
+
+    pub fn is_set(&self, opt: FindOption) -> bool {
+        match opt {
+            FindOption::CaseSensitive => self.case_sensitive,
+            FindOption::WholeWord => self.whole_word,
+            FindOption::Regex => self.regex,
+        }
+    }
+
+    fn pattern(&self) -> Result<Option<Regex>, String> {
+        if self.query.is_empty() {
+            return Ok(None);
+        }
+        let mut pat = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        if self.whole_word {
+            pat = format!(r"\b(?:{pat})\b");
+        }
+        RegexBuilder::new(&pat)
+            .case_insensitive(!self.case_sensitive)
+            .multi_line(true)
+            .build()
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Update the matches for the text, the current match is the
+    /// first one at or after the cursor (in bytes)
+    pub fn search(&mut self, text: &str, cursor: usize) {
+        self.matches.clear();
+        self.current = 0;
+        match self.pattern() {
+            Ok(Some(re)) => {
+                self.error = None;
+                self.matches = re
+                    .find_iter(text)
+                    // empty matches can't be selected or replaced
+                    .filter(|m| !m.is_empty())
+                    .map(|m| m.range())
+                    .collect();
+                self.current = self
+                    .matches
+                    .iter()
+                    .position(|m| m.start >= cursor)
+                    .unwrap_or(0);
+            }
+            Ok(None) => self.error = None,
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Current match, None if it is no longer in the text
+    pub fn current_match(&self, text: &str) -> Option<Range<usize>> {
+        self.matches
+            .get(self.current)
+            .filter(|m| text.get((*m).clone()).is_some())
+            .cloned()
+    }
+
+    pub fn next(&mut self) {
+        if !self.matches.is_empty() {
+            self.current = (self.current + 1) % self.matches.len();
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if !self.matches.is_empty() {
+            let n = self.matches.len();
+            self.current = (self.current + n - 1) % n;
+        }
+    }
+
+    /// Text to replace the current match with, the capture groups
+    /// (`$1`, `${name}`) are expanded in the regex mode
+    pub fn replacement_for(&self, text: &str) -> Option<String> {
+        let range = self.current_match(text)?;
+        let re = self.pattern().ok()??;
+        let mut dst = String::new();
+        self.expand(&re, text, range.start, &mut dst)?;
+        Some(dst)
+    }
+
+    /// Text with all the matches replaced, None if the matches are
+    /// not from the text
+    pub fn replace_all(&self, text: &str) -> Option<String> {
+        let re = self.pattern().ok()??;
+        let mut new = String::with_capacity(text.len());
+        let mut last = 0;
+        for m in &self.matches {
+            new.push_str(text.get(last..m.start)?);
+            text.get(m.clone())?;
+            self.expand(&re, text, m.start, &mut new)?;
+            last = m.end;
+        }
+        new.push_str(&text[last..]);
+        Some(new)
+    }
+
+    fn expand(&self, re: &Regex, text: &str, start: usize, dst: &mut String) -> Option<()> {
+        if self.regex {
+            re.captures_at(text, start)?.expand(&self.replacement, dst);
+        } else {
+            dst.push_str(&self.replacement);
+        }
+        Some(())
+    }
+
+    pub fn status(&self) -> String {
+        if let Some(e) = &self.error {
+            // the regex errors span multiple lines
+            e.lines().last().unwrap_or_default().trim().to_string()
+        } else if self.matches.is_empty() {
+            "No matches".to_string()
+        } else {
+            format!("{}/{}", self.current + 1, self.matches.len())
+        }
+    }
+}
+
+/// Line and column (in chars) of the byte offset in the text
+pub fn position(text: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line, before[start..].chars().count())
+}
+
+/// Byte offset of the line and column (in chars) in the text
+pub fn offset(text: &str, (line, col): (usize, usize)) -> usize {
+    let start: usize = text.split('\n').take(line).map(|l| l.len() + 1).sum();
+    let start = start.min(text.len());
+    start
+        + text[start..]
+            .char_indices()
+            .take_while(|(_, c)| *c != '\n')
+            .nth(col)
+            .map(|(i, _)| i)
+            .unwrap_or_else(|| text[start..].find('\n').unwrap_or(text.len() - start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(query: &str) -> Find {
+        Find {
+            query: query.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn search_options() {
+        let text = "cat Cat concat cat";
+        let mut f = find("cat");
+        f.search(text, 0);
+        assert_eq!(f.matches, [0..3, 4..7, 11..14, 15..18]);
+        f.toggle(FindOption::CaseSensitive);
+        f.search(text, 0);
+        assert_eq!(f.matches, [0..3, 11..14, 15..18]);
+        f.toggle(FindOption::WholeWord);
+        f.search(text, 0);
+        assert_eq!(f.matches, [0..3, 15..18]);
+        assert_eq!(f.status(), "1/2");
+    }
+
+    #[test]
+    fn search_from_cursor() {
+        let mut f = find("é");
+        f.search("é a é", 1);
+        assert_eq!(f.matches, [0..2, 5..7]);
+        assert_eq!(f.current, 1);
+        // wraps around to the first one
+        f.search("é a é", 6);
+        assert_eq!(f.current, 0);
+    }
+
+    #[test]
+    fn invalid_regex() {
+        let mut f = find("(a");
+        f.regex = true;
+        f.search("(a", 0);
+        assert!(f.matches.is_empty());
+        assert!(f.error.is_some());
+    }
+
+    #[test]
+    fn replace_all() {
+        let mut f = find("a1");
+        f.replacement = "<$1>".to_string();
+        f.search("a1 é a1", 0);
+        assert_eq!(f.replace_all("a1 é a1").unwrap(), "<$1> é <$1>");
+        f.query = "a(\\d)".to_string();
+        f.regex = true;
+        f.search("a1 é a2", 0);
+        assert_eq!(f.replace_all("a1 é a2").unwrap(), "<1> é <2>");
+        // matches are from another text
+        assert_eq!(f.replace_all("a1"), None);
+    }
+
+    #[test]
+    fn position_in_chars() {
+        let text = "aé\nüx";
+        assert_eq!(position(text, 0), (0, 0));
+        assert_eq!(position(text, 3), (0, 2));
+        assert_eq!(position(text, 6), (1, 1));
+        // inside a char
+        assert_eq!(position(text, 2), (0, 1));
+        assert_eq!(position(text, 100), (1, 2));
+    }
+
+    #[test]
+    fn offset_in_bytes() {
+        let text = "aé\nüx";
+        assert_eq!(offset(text, (0, 1)), 1);
+        assert_eq!(offset(text, (0, 2)), 3);
+        assert_eq!(offset(text, (1, 1)), 6);
+        // past the end of the line
+        assert_eq!(offset(text, (0, 5)), 3);
+        assert_eq!(offset(text, (5, 0)), text.len());
+        for i in [0, 1, 3, 4, 6, 7] {
+            assert_eq!(offset(text, position(text, i)), i);
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Formatter for the tasks and network files.
+//!
+//! The buffer is re-emitted from its tokens with the whitespace
+//! normalized; comments and strings (including the multi-line ones)
+//! are kept as they are.
+use super::my_hl::NadiFileType;
+use nadi_core::parser::tokenizer::{TaskToken, Token, get_tokens};
+use nadi_core::tasks::TaskKeyword;
+
+static INDENT: &str = "    ";
+
+/// Token with the operators made of multiple tokens (`==`, `<=`, `>=`
+/// and `!=`) merged together
+struct Unit {
+    ty: TaskToken,
+    content: String,
+    /// token is an operator that goes between spaces
+    op: bool,
+}
+
+pub fn format(text: &str, nft: &NadiFileType) -> Result<String, String> {
+    let tokens = get_tokens(text).map_err(|e| {
+        format!(
+            "Invalid token at line {} column {}",
+            e.line + 1,
+            e.col.max(1)
+        )
+    })?;
+    let lines = match nft {
+        NadiFileType::Tasks => format_tasks(&tokens),
+        NadiFileType::Network => format_network(&tokens),
+        _ => return Err("Formatting is only supported for tasks and network files".into()),
+    };
+    Ok(join_lines(lines))
+}
+
+/// Join the lines removing the extra blank lines, the output always
+/// ends with a newline
+fn join_lines(lines: Vec<String>) -> String {
+    let mut out = String::new();
+    let mut blank = true;
+    for line in lines {
+        let line = line.trim_end();
+        if line.is_empty() {
+            if !blank {
+                out.push('\n');
+            }
+            blank = true;
+        } else {
+            out.push_str(line);
+            out.push('\n');
+            blank = false;
+        }
+    }
+    if out.ends_with("\n\n") {
+        out.pop();
+    }
+    out
+}
+
+/// Split the tokens at newlines, dropping the whitespace
+fn split_lines<'a, 'b>(tokens: &'b [Token<'a>]) -> Vec<Vec<&'b Token<'a>>> {
+    let mut lines = vec![vec![]];
+    for token in tokens {
+        match token.ty {
+            TaskToken::NewLine => lines.push(vec![]),
+            TaskToken::WhiteSpace => (),
+            _ => lines.last_mut().expect("starts with a line").push(token),
+        }
+    }
+    lines
+}
+
+fn is_open(ty: &TaskToken) -> bool {
+    matches!(
+        ty,
+        TaskToken::ParenStart | TaskToken::BracketStart | TaskToken::BraceStart
+    )
+}
+
+fn is_close(ty: &TaskToken) -> bool {
+    matches!(
+        ty,
+        TaskToken::ParenEnd | TaskToken::BracketEnd | TaskToken::BraceEnd
+    )
+}
+
+fn is_task_keyword(ty: &TaskToken) -> bool {
+    matches!(
+        ty,
+        TaskToken::Keyword(TaskKeyword::Node | TaskKeyword::Network | TaskKeyword::Env)
+    )
+}
+
+/// Merge the operators, the tokens in a line are adjacent in the
+/// source only if there was no whitespace between them
+fn units(tokens: &[Token]) -> Vec<Vec<Unit>> {
+    let mut lines: Vec<Vec<Unit>> = vec![vec![]];
+    let mut adjacent = false;
+    // inside the `<...>` propagation
+    let mut in_angle = false;
+    for token in tokens {
+        let line = lines.last_mut().expect("starts with a line");
+        match &token.ty {
+            TaskToken::NewLine => {
+                lines.push(vec![]);
+                adjacent = false;
+                continue;
+            }
+            TaskToken::WhiteSpace => {
+                adjacent = false;
+                continue;
+            }
+            _ => (),
+        }
+        let prev = line.last_mut();
+        match (&token.ty, prev) {
+            (TaskToken::AngleStart, Some(p)) if is_task_keyword(&p.ty) => {
+                in_angle = true;
+                line.push(Unit {
+                    ty: token.ty.clone(),
+                    content: token.content.to_string(),
+                    op: false,
+                });
+            }
+            (TaskToken::AngleEnd, _) if in_angle => {
+                in_angle = false;
+                line.push(Unit {
+                    ty: token.ty.clone(),
+                    content: token.content.to_string(),
+                    op: false,
+                });
+            }
+            (TaskToken::Assignment, Some(p))
+                if adjacent && p.op && matches!(p.content.as_str(), "=" | "<" | ">" | "!") =>
+            {
+                p.content.push('=');
+            }
+            (ty, _) => {
+                let op = matches!(
+                    ty,
+                    TaskToken::Assignment
+                        | TaskToken::AngleStart
+                        | TaskToken::AngleEnd
+                        | TaskToken::And
+                        | TaskToken::Or
+                        | TaskToken::PathSep
+                        | TaskToken::Keyword(TaskKeyword::In | TaskKeyword::Match)
+                ) || (*ty == TaskToken::Not);
+                line.push(Unit {
+                    ty: ty.clone(),
+                    content: token.content.to_string(),
+                    op,
+                });
+            }
+        }
+        adjacent = true;
+    }
+    lines
+}
+
+/// Whether there should be a space between the two units
+fn space_between(prev: &Unit, next: &Unit, before_prev: Option<&Unit>) -> bool {
+    use TaskToken as T;
+    if next.ty == T::Comment {
+        return true;
+    }
+    // `!x` and `!=` are different
+    let unary_not = |u: &Unit| u.ty == T::Not && u.content == "!";
+    // `node(=x)` and `node(==x)` for the strict propositions
+    let prefix = |u: &Unit, before: Option<&Unit>| {
+        matches!(u.content.as_str(), "=" | "==") && before.is_some_and(|b| b.ty == T::ParenStart)
+    };
+    if is_close(&next.ty) || (matches!(next.ty, T::Comma | T::Dot | T::AngleEnd) && !next.op) {
+        return false;
+    }
+    if is_open(&prev.ty) || matches!(prev.ty, T::Dot) || (prev.ty == T::AngleStart && !prev.op) {
+        return false;
+    }
+    if unary_not(prev) || prefix(prev, before_prev) {
+        return false;
+    }
+    if next.op && !unary_not(next) {
+        return !prefix(next, Some(prev));
+    }
+    if prev.op || prev.ty == T::Comma {
+        return true;
+    }
+    match next.ty {
+        // function calls and the propagation after the keywords
+        T::ParenStart | T::BracketStart => !(prev.ty == T::Function || is_task_keyword(&prev.ty)),
+        T::AngleStart => !is_task_keyword(&prev.ty),
+        _ => true,
+    }
+}
+
+fn format_tasks(tokens: &[Token]) -> Vec<String> {
+    let mut depth: usize = 0;
+    let mut out = vec![];
+    for line in units(tokens) {
+        let starts_closed = line.first().is_some_and(|u| is_close(&u.ty));
+        let mut text = INDENT.repeat(depth.saturating_sub(starts_closed as usize));
+        for (i, unit) in line.iter().enumerate() {
+            if i > 0 && space_between(&line[i - 1], unit, i.checked_sub(2).map(|j| &line[j])) {
+                text.push(' ');
+            }
+            text.push_str(&unit.content);
+            if is_open(&unit.ty) {
+                depth += 1;
+            } else if is_close(&unit.ty) {
+                depth = depth.saturating_sub(1);
+            }
+        }
+        out.push(text);
+    }
+    out
+}
+
+/// Network files have one connection per line, the `->` are aligned
+fn format_network(tokens: &[Token]) -> Vec<String> {
+    let lines: Vec<(String, Option<String>, Option<&str>)> = split_lines(tokens)
+        .into_iter()
+        .map(|line| {
+            let comment = line
+                .iter()
+                .find(|t| t.ty == TaskToken::Comment)
+                .map(|t| t.content);
+            let code: Vec<&str> = line
+                .iter()
+                .filter(|t| t.ty != TaskToken::Comment)
+                .map(|t| t.content)
+                .collect();
+            match code.iter().position(|c| *c == "->") {
+                Some(i) => (code[..i].join(" "), Some(code[i + 1..].join(" ")), comment),
+                None => (code.join(" "), None, comment),
+            }
+        })
+        .collect();
+    let width = lines
+        .iter()
+        .filter(|l| l.1.is_some())
+        .map(|l| l.0.chars().count())
+        .max()
+        .unwrap_or_default();
+    lines
+        .into_iter()
+        .map(|(input, output, comment)| {
+            let mut text = match output {
+                Some(o) => format!("{input:<width$} -> {o}"),
+                None => input,
+            };
+            if let Some(c) = comment {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(c);
+            }
+            text
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nadi_core::parser::{network, tasks};
+    use std::path::PathBuf;
+
+    /// Example files with the extension
+    fn examples(ext: &str) -> Vec<PathBuf> {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("examples");
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.extension().is_some_and(|e| e == ext))
+            .collect();
+        files.sort();
+        assert!(!files.is_empty(), "no .{ext} examples");
+        files
+    }
+
+    /// Formatted text, checking that formatting it again changes
+    /// nothing
+    fn format_twice(path: &PathBuf, nft: &NadiFileType) -> (String, String) {
+        let text = std::fs::read_to_string(path).unwrap();
+        let once = format(&text, nft).unwrap();
+        let twice = format(&once, nft).unwrap();
+        assert_eq!(once, twice, "{} is not stable", path.display());
+        (text, once)
+    }
+
+    #[test]
+    fn tasks_round_trip() {
+        for path in examples("tasks") {
+            let (text, formatted) = format_twice(&path, &NadiFileType::Tasks);
+            let before = tasks::parse(get_tokens(&text).unwrap()).unwrap();
+            let after = tasks::parse(get_tokens(&formatted).unwrap()).unwrap();
+            assert_eq!(before, after, "{} tasks changed", path.display());
+        }
+    }
+
+    #[test]
+    fn network_round_trip() {
+        for path in examples("network") {
+            let (text, formatted) = format_twice(&path, &NadiFileType::Network);
+            let before = network::parse(get_tokens(&text).unwrap()).unwrap();
+            let after = network::parse(get_tokens(&formatted).unwrap()).unwrap();
+            assert_eq!(before, after, "{} network changed", path.display());
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Undo and redo history for the editor.
+use iced::widget::text_editor::{Action, Edit};
+
+/// Maximum number of steps to keep in the history
+static HISTORY_LEN: usize = 200;
+
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub text: String,
+    /// line and column (in chars)
+    pub cursor: (usize, usize),
+}
+
+/// Kind of edit, consecutive edits of the same kind are undone
+/// together
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditKind {
+    Typing,
+    Deleting,
+    /// edits that are always their own step (paste, replace, etc)
+    Other,
+}
+
+impl EditKind {
+    pub fn from_action(action: &Action) -> Self {
+        match action {
+            Action::Edit(Edit::Insert(c)) if !c.is_whitespace() => Self::Typing,
+            Action::Edit(Edit::Backspace | Edit::Delete) => Self::Deleting,
+            _ => Self::Other,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    /// kind of the last edit, None if the group was broken by moving
+    /// the cursor
+    last: Option<EditKind>,
+}
+
+impl History {
+    /// Whether an edit of the kind starts a new step, consecutive
+    /// edits of the same kind are grouped. The buffer before the
+    /// edit is then saved with [`History::push`], so typing doesn't
+    /// copy the buffer on each key
+    pub fn starts_step(&mut self, kind: EditKind) -> bool {
+        self.redo.clear();
+        let grouped = kind != EditKind::Other && self.last == Some(kind);
+        self.last = Some(kind);
+        !grouped
+    }
+
+    /// Save the buffer before the edit that started a step
+    pub fn push(&mut self, before: Snapshot) {
+        self.undo.push(before);
+        if self.undo.len() > HISTORY_LEN {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Start a new step on the next edit
+    pub fn break_group(&mut self) {
+        self.last = None;
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Buffer to go back to, the current one is saved for redo
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let prev = self.undo.pop()?;
+        self.redo.push(current);
+        self.last = None;
+        Some(prev)
+    }
+
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        self.last = None;
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snap(text: &str) -> Snapshot {
+        Snapshot {
+            text: text.to_string(),
+            cursor: (0, text.chars().count()),
+        }
+    }
+
+    /// Simulate an edit from the buffer `before`
+    fn edit(h: &mut History, kind: EditKind, before: &str) {
+        if h.starts_step(kind) {
+            h.push(snap(before));
+        }
+    }
+
+    #[test]
+    fn edit_kinds() {
+        let kind = |e| EditKind::from_action(&Action::Edit(e));
+        assert_eq!(kind(Edit::Insert('é')), EditKind::Typing);
+        assert_eq!(kind(Edit::Insert(' ')), EditKind::Other);
+        assert_eq!(kind(Edit::Backspace), EditKind::Deleting);
+        assert_eq!(kind(Edit::Enter), EditKind::Other);
+    }
+
+    #[test]
+    fn typing_is_grouped() {
+        let mut h = History::default();
+        edit(&mut h, EditKind::Typing, "");
+        edit(&mut h, EditKind::Typing, "a");
+        edit(&mut h, EditKind::Deleting, "ab");
+        edit(&mut h, EditKind::Deleting, "a");
+        assert_eq!(h.undo(snap("")).unwrap().text, "ab");
+        assert_eq!(h.undo(snap("ab")).unwrap().text, "");
+        assert!(!h.can_undo());
+    }
+
+    #[test]
+    fn other_edits_are_not_grouped() {
+        let mut h = History::default();
+        edit(&mut h, EditKind::Other, "");
+        edit(&mut h, EditKind::Other, "a");
+        assert_eq!(h.undo(snap("ab")).unwrap().text, "a");
+    }
+
+    #[test]
+    fn break_group() {
+        let mut h = History::default();
+        edit(&mut h, EditKind::Typing, "");
+        h.break_group();
+        edit(&mut h, EditKind::Typing, "a");
+        assert_eq!(h.undo(snap("ab")).unwrap().text, "a");
+        // undoing also starts a new step
+        edit(&mut h, EditKind::Typing, "a");
+        assert_eq!(h.undo(snap("ac")).unwrap().text, "a");
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut h = History::default();
+        edit(&mut h, EditKind::Other, "a");
+        let prev = h.undo(snap("b")).unwrap();
+        assert_eq!(prev.text, "a");
+        assert_eq!(h.redo(prev).unwrap().text, "b");
+        assert!(!h.can_redo());
+        h.undo(snap("b"));
+        // new edit drops the redo
+        edit(&mut h, EditKind::Typing, "a");
+        assert!(!h.can_redo());
+    }
+
+    #[test]
+    fn history_len() {
+        let mut h = History::default();
+        for i in 0..(HISTORY_LEN + 10) {
+            edit(&mut h, EditKind::Other, &i.to_string());
+        }
+        let mut count = 0;
+        let mut last = String::new();
+        while let Some(s) = h.undo(snap("")) {
+            count += 1;
+            last = s.text;
+        }
+        assert_eq!(count, HISTORY_LEN);
+        assert_eq!(last, "10");
+    }
+}
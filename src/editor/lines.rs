@@ -0,0 +1,230 @@
+//! Editing commands that work on whole lines.
+use std::ops::Range;
+
+static INDENT: &str = "    ";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineCommand {
+    ToggleComment,
+    Duplicate,
+    MoveUp,
+    MoveDown,
+    Indent,
+    Dedent,
+}
+
+/// Comment prefix for the file types that use `#` comments
+pub fn comment_prefix(ext: &str) -> Option<&'static str> {
+    match ext {
+        "task" | "tasks" | "net" | "network" | "toml" | "py" | "sh" => Some("#"),
+        _ => None,
+    }
+}
+
+/// Result of a line command: the new text, the new line range of
+/// the lines it was applied to, and the cursor position (column in
+/// chars)
+pub struct LineEdit {
+    pub text: String,
+    pub lines: Range<usize>,
+    pub cursor: (usize, usize),
+}
+
+/// Apply the command on the lines of the text, None if it does
+/// nothing (e.g. moving the first line up). The cursor column is in
+/// bytes like the editor cursor.
+pub fn apply(
+    text: &str,
+    lines: Range<usize>,
+    cursor: (usize, usize),
+    cmd: LineCommand,
+    comment: Option<&str>,
+) -> Option<LineEdit> {
+    let mut all: Vec<String> = text.split('\n').map(String::from).collect();
+    let lines = lines.start.min(all.len() - 1)..lines.end.min(all.len());
+    let (line, col) = cursor;
+    let mut new_lines = lines.clone();
+    let mut new_cursor = cursor;
+    match cmd {
+        LineCommand::ToggleComment => {
+            let prefix = comment?;
+            let block = &mut all[lines.clone()];
+            let non_blank = || block.iter().filter(|l| !l.trim().is_empty());
+            let commented = non_blank().all(|l| l.trim_start().starts_with(prefix));
+            if commented {
+                for (i, l) in block.iter_mut().enumerate() {
+                    let indent = l.len() - l.trim_start().len();
+                    let Some(rest) = l[indent..].strip_prefix(prefix) else {
+                        continue;
+                    };
+                    let rest = rest.strip_prefix(' ').unwrap_or(rest);
+                    let removed = l.len() - indent - rest.len();
+                    *l = format!("{}{rest}", &l[..indent]);
+                    if lines.start + i == line && col > indent {
+                        new_cursor.1 = col.saturating_sub(removed).max(indent);
+                    }
+                }
+            } else {
+                // comment at the same indentation for all the lines
+                let indent = non_blank().map(|l| l.len() - l.trim_start().len()).min()?;
+                for (i, l) in block.iter_mut().enumerate() {
+                    if l.trim().is_empty() {
+                        continue;
+                    }
+                    l.insert_str(indent, &format!("{prefix} "));
+                    if lines.start + i == line && col >= indent {
+                        new_cursor.1 = col + prefix.len() + 1;
+                    }
+                }
+            }
+        }
+        LineCommand::Duplicate => {
+            let block: Vec<String> = all[lines.clone()].to_vec();
+            let n = block.len();
+            all.splice(lines.end..lines.end, block);
+            new_lines = (lines.start + n)..(lines.end + n);
+            new_cursor.0 = line + n;
+        }
+        LineCommand::MoveUp => {
+            if lines.start == 0 {
+                return None;
+            }
+            let above = all.remove(lines.start - 1);
+            all.insert(lines.end - 1, above);
+            new_lines = (lines.start - 1)..(lines.end - 1);
+            new_cursor.0 = line - 1;
+        }
+        LineCommand::MoveDown => {
+            if lines.end >= all.len() {
+                return None;
+            }
+            let below = all.remove(lines.end);
+            all.insert(lines.start, below);
+            new_lines = (lines.start + 1)..(lines.end + 1);
+            new_cursor.0 = line + 1;
+        }
+        LineCommand::Indent => {
+            for (i, l) in all[lines.clone()].iter_mut().enumerate() {
+                if l.is_empty() {
+                    continue;
+                }
+                l.insert_str(0, INDENT);
+                if lines.start + i == line {
+                    new_cursor.1 = col + INDENT.len();
+                }
+            }
+        }
+        LineCommand::Dedent => {
+            for (i, l) in all[lines.clone()].iter_mut().enumerate() {
+                let removed = if l.starts_with('\t') {
+                    1
+                } else {
+                    l.chars()
+                        .take(INDENT.len())
+                        .take_while(|c| *c == ' ')
+                        .count()
+                };
+                l.drain(..removed);
+                if lines.start + i == line {
+                    new_cursor.1 = col.saturating_sub(removed);
+                }
+            }
+        }
+    }
+    // the columns above are all in bytes
+    new_cursor.1 = all
+        .get(new_cursor.0)
+        .map_or(0, |l| l.get(..new_cursor.1).unwrap_or(l).chars().count());
+    let new_text = all.join("\n");
+    if new_text == text {
+        return None;
+    }
+    Some(LineEdit {
+        text: new_text,
+        lines: new_lines,
+        cursor: new_cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply_on(
+        text: &str,
+        lines: Range<usize>,
+        cursor: (usize, usize),
+        cmd: LineCommand,
+    ) -> Option<LineEdit> {
+        apply(text, lines, cursor, cmd, Some("#"))
+    }
+
+    #[test]
+    fn toggle_comment() {
+        // cursor is after the `ü`, at byte 4 and char 3
+        let edit = apply_on("é = 1\n  ü = 2", 0..2, (1, 4), LineCommand::ToggleComment).unwrap();
+        assert_eq!(edit.text, "# é = 1\n#   ü = 2");
+        assert_eq!(edit.lines, 0..2);
+        assert_eq!(edit.cursor, (1, 5));
+        let edit = apply_on(&edit.text, 0..2, (1, 6), LineCommand::ToggleComment).unwrap();
+        assert_eq!(edit.text, "é = 1\n  ü = 2");
+        assert_eq!(edit.cursor, (1, 3));
+    }
+
+    #[test]
+    fn comment_at_same_indent() {
+        let edit = apply_on("  a\n\n    b", 0..3, (0, 0), LineCommand::ToggleComment).unwrap();
+        assert_eq!(edit.text, "  # a\n\n  #   b");
+        // the cursor before the comment doesn't move
+        assert_eq!(edit.cursor, (0, 0));
+    }
+
+    #[test]
+    fn no_comment_prefix() {
+        let edit = apply("a", 0..1, (0, 0), LineCommand::ToggleComment, None);
+        assert!(edit.is_none());
+        assert_eq!(comment_prefix("tasks"), Some("#"));
+        assert_eq!(comment_prefix("md"), None);
+    }
+
+    #[test]
+    fn duplicate() {
+        let edit = apply_on("aé\nb", 0..1, (0, 3), LineCommand::Duplicate).unwrap();
+        assert_eq!(edit.text, "aé\naé\nb");
+        assert_eq!(edit.lines, 1..2);
+        assert_eq!(edit.cursor, (1, 2));
+    }
+
+    #[test]
+    fn move_lines() {
+        assert!(apply_on("a\nb", 0..1, (0, 0), LineCommand::MoveUp).is_none());
+        assert!(apply_on("a\nb", 1..2, (1, 0), LineCommand::MoveDown).is_none());
+        let edit = apply_on("a\nb\nc", 0..2, (1, 1), LineCommand::MoveDown).unwrap();
+        assert_eq!(edit.text, "c\na\nb");
+        assert_eq!(edit.lines, 1..3);
+        assert_eq!(edit.cursor, (2, 1));
+        let edit = apply_on(&edit.text, 1..3, (2, 1), LineCommand::MoveUp).unwrap();
+        assert_eq!(edit.text, "a\nb\nc");
+        assert_eq!(edit.lines, 0..2);
+    }
+
+    #[test]
+    fn indent_dedent() {
+        let edit = apply_on("é\n\n\tx", 0..3, (0, 2), LineCommand::Indent).unwrap();
+        assert_eq!(edit.text, "    é\n\n    \tx");
+        assert_eq!(edit.cursor, (0, 5));
+        let edit = apply_on(&edit.text, 0..3, (0, 6), LineCommand::Dedent).unwrap();
+        assert_eq!(edit.text, "é\n\n\tx");
+        assert_eq!(edit.cursor, (0, 1));
+        let edit = apply_on("\tx\n  y", 0..2, (1, 3), LineCommand::Dedent).unwrap();
+        assert_eq!(edit.text, "x\ny");
+        assert_eq!(edit.cursor, (1, 1));
+    }
+
+    #[test]
+    fn lines_past_the_end() {
+        let edit = apply_on("a", 3..5, (0, 0), LineCommand::Indent).unwrap();
+        assert_eq!(edit.text, "    a");
+        assert_eq!(edit.lines, 0..1);
+    }
+}
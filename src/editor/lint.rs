@@ -0,0 +1,310 @@
+//! Checks for the tasks against the loaded functions, so the typos
+//! and wrong arguments are found before running them.
+use super::complete::Symbols;
+use super::diagnostics::Diagnostic;
+use super::template;
+use crate::help::FuncType;
+use crate::help::index::base_type;
+use crate::help::page::ArgHelp;
+use nadi_core::attrs::Attribute;
+use nadi_core::parser::tasks;
+use nadi_core::parser::tokenizer::{self, TaskToken, Token};
+use nadi_core::tasks::{FunctionCall, TaskInput, TaskKeyword, TaskType};
+use std::ops::Range;
+
+/// Position of a token in the buffer
+#[derive(Clone)]
+pub(super) struct Located<'a> {
+    pub(super) line: usize,
+    cols: Range<usize>,
+    content: &'a str,
+}
+
+/// Tokens of a single task: the keyword it starts with, and the
+/// function names and strings in it
+pub(super) struct TaskTokens<'a> {
+    pub(super) keyword: Located<'a>,
+    functions: Vec<Located<'a>>,
+    strings: Vec<(String, Located<'a>)>,
+    /// last line with the code of the task
+    pub(super) last_line: usize,
+}
+
+impl TaskTokens<'_> {
+    /// Position of the function call, the ones already used are
+    /// removed so the same function called twice gets both positions
+    fn take(&mut self, name: &str) -> (usize, Range<usize>) {
+        let loc = match self.functions.iter().position(|f| f.content == name) {
+            Some(i) => self.functions.remove(i),
+            None => self.keyword.clone(),
+        };
+        (loc.line, loc.cols)
+    }
+
+    /// Position of the string with the value, same as [`Self::take`]
+    fn take_string(&mut self, value: &str) -> (usize, Range<usize>) {
+        let loc = match self.strings.iter().position(|(s, _)| *s == value) {
+            Some(i) => self.strings.remove(i).1,
+            None => self.keyword.clone(),
+        };
+        (loc.line, loc.cols)
+    }
+}
+
+/// Split the tokens into the tasks, same as the parser does
+pub(super) fn task_tokens<'a>(tokens: &[Token<'a>]) -> Vec<TaskTokens<'a>> {
+    let mut tasks: Vec<TaskTokens> = vec![];
+    let (mut line, mut col) = (0, 0);
+    // `help node` is a single task
+    let mut after_help = false;
+    for token in tokens {
+        let len = token.content.chars().count();
+        let loc = Located {
+            line,
+            cols: col..(col + len),
+            content: token.content,
+        };
+        match &token.ty {
+            TaskToken::NewLine => after_help = false,
+            TaskToken::Keyword(TaskKeyword::End) => break,
+            TaskToken::Keyword(
+                kw @ (TaskKeyword::Node
+                | TaskKeyword::Network
+                | TaskKeyword::Env
+                | TaskKeyword::Help
+                | TaskKeyword::Exit),
+            ) => {
+                if !after_help {
+                    tasks.push(TaskTokens {
+                        keyword: loc,
+                        functions: vec![],
+                        strings: vec![],
+                        last_line: line,
+                    });
+                }
+                after_help = *kw == TaskKeyword::Help;
+            }
+            TaskToken::Function => {
+                if let Some(t) = tasks.last_mut() {
+                    t.functions.push(loc);
+                }
+            }
+            TaskToken::String(s) => {
+                if let Some(t) = tasks.last_mut() {
+                    t.strings.push((s.clone(), loc));
+                }
+            }
+            _ => (),
+        }
+        match token.content.rsplit_once('\n') {
+            Some((before, after)) => {
+                line += before.matches('\n').count() + 1;
+                col = after.chars().count();
+            }
+            None => col += len,
+        }
+        let code = !matches!(
+            token.ty,
+            TaskToken::NewLine | TaskToken::WhiteSpace | TaskToken::Comment
+        );
+        if let (true, Some(t)) = (code, tasks.last_mut()) {
+            t.last_line = line;
+        }
+    }
+    tasks
+}
+
+/// Check the function calls in the tasks, the text is assumed to
+/// have no parse errors
+pub fn check(text: &str, symbols: &Symbols) -> Vec<Diagnostic> {
+    let Ok(tokens) = tokenizer::get_tokens(text) else {
+        return vec![];
+    };
+    let mut locations = task_tokens(&tokens);
+    let Ok(tasks) = tasks::parse(tokens) else {
+        return vec![];
+    };
+    let mut diags = vec![];
+    for (task, loc) in tasks.iter().zip(locations.iter_mut()) {
+        let ty = match task.ty {
+            TaskType::Node(_) => FuncType::Node,
+            TaskType::Network(_) => FuncType::Network,
+            TaskType::Env => FuncType::Env,
+            TaskType::Help(..) | TaskType::Exit => continue,
+        };
+        match &task.input {
+            TaskInput::Function(call) => check_call(call, ty, loc, symbols, &mut diags),
+            TaskInput::Literal(Attribute::String(s))
+                if task.attribute.as_deref() == Some("nodelabel") =>
+            {
+                check_template(s, loc, &mut diags)
+            }
+            _ => (),
+        }
+    }
+    diags
+}
+
+/// Arguments of the function, node and network tasks can also call
+/// the env functions
+fn function_args<'a>(symbols: &'a Symbols, ty: &FuncType, name: &str) -> Option<&'a [ArgHelp]> {
+    symbols
+        .args(ty, name)
+        .or_else(|| symbols.args(&FuncType::Env, name))
+}
+
+fn check_call(
+    call: &FunctionCall,
+    ty: FuncType,
+    loc: &mut TaskTokens,
+    symbols: &Symbols,
+    diags: &mut Vec<Diagnostic>,
+) {
+    let (line, cols) = loc.take(&call.name);
+    let name = &call.name;
+    let Some(args) = function_args(symbols, &ty, name) else {
+        diags.push(Diagnostic::error(
+            line,
+            cols,
+            format!("{ty} function `{name}` not found"),
+        ));
+        return;
+    };
+    let mut given = vec![false; args.len()];
+    let variadic = args.iter().position(|a| a.variadic == "*");
+    let kw_variadic = args.iter().any(|a| a.variadic == "**");
+
+    for (i, input) in call.args.iter().enumerate() {
+        let ind = match variadic {
+            Some(v) if i >= v => v,
+            _ => i,
+        };
+        match args.get(ind).filter(|a| a.variadic != "**") {
+            Some(arg) => {
+                given[ind] = true;
+                check_input(input, arg, name, (line, &cols), loc, symbols, diags);
+            }
+            None => {
+                let max = args.iter().filter(|a| a.variadic.is_empty()).count();
+                diags.push(Diagnostic::error(
+                    line,
+                    cols.clone(),
+                    format!("Too many arguments for `{name}`, it takes at most {max}"),
+                ));
+                break;
+            }
+        }
+    }
+
+    // sorted so the messages are in the same order every time
+    let mut kwargs: Vec<(&String, &TaskInput)> = call.kwargs.iter().collect();
+    kwargs.sort_by_key(|(k, _)| *k);
+    for (key, input) in kwargs {
+        match args
+            .iter()
+            .position(|a| &a.name == key && a.variadic.is_empty())
+        {
+            Some(ind) => {
+                if given[ind] {
+                    diags.push(Diagnostic::error(
+                        line,
+                        cols.clone(),
+                        format!("Argument `{key}` of `{name}` is given twice"),
+                    ));
+                }
+                given[ind] = true;
+                check_input(input, &args[ind], name, (line, &cols), loc, symbols, diags);
+            }
+            None if kw_variadic => check_nested(input, loc, symbols, diags),
+            None => diags.push(Diagnostic::error(
+                line,
+                cols.clone(),
+                format!("Unknown argument `{key}` for `{name}`"),
+            )),
+        }
+    }
+
+    for (arg, given) in args.iter().zip(given) {
+        if arg.required && !given {
+            diags.push(Diagnostic::error(
+                line,
+                cols.clone(),
+                format!("Missing required argument `{}` for `{name}`", arg.name),
+            ));
+        }
+    }
+}
+
+/// Check the function calls used as arguments, they are always env
+/// functions
+fn check_nested(
+    input: &TaskInput,
+    loc: &mut TaskTokens,
+    symbols: &Symbols,
+    diags: &mut Vec<Diagnostic>,
+) {
+    if let TaskInput::Function(call) = input {
+        check_call(call, FuncType::Env, loc, symbols, diags);
+    }
+}
+
+fn check_input(
+    input: &TaskInput,
+    arg: &ArgHelp,
+    name: &str,
+    (line, cols): (usize, &Range<usize>),
+    loc: &mut TaskTokens,
+    symbols: &Symbols,
+    diags: &mut Vec<Diagnostic>,
+) {
+    match input {
+        TaskInput::Literal(val) if !accepts(&arg.ty, val) => {
+            diags.push(Diagnostic::warning(
+                line,
+                cols.clone(),
+                format!(
+                    "Argument `{}` of `{name}` expects `{}`, found {}",
+                    arg.name,
+                    base_type(&arg.ty),
+                    val.type_name()
+                ),
+            ));
+        }
+        TaskInput::Literal(Attribute::String(s)) if template::is_template(&arg.ty) => {
+            check_template(s, loc, diags)
+        }
+        _ => check_nested(input, loc, symbols, diags),
+    }
+}
+
+fn check_template(value: &str, loc: &mut TaskTokens, diags: &mut Vec<Diagnostic>) {
+    if let Some(err) = template::error(value) {
+        let (line, cols) = loc.take_string(value);
+        diags.push(Diagnostic::error(line, cols, err));
+    }
+}
+
+/// Whether the literal value can be converted into the argument
+/// type, the types that are not known are always accepted
+fn accepts(ty: &str, val: &Attribute) -> bool {
+    let ty = base_type(ty);
+    match ty.as_str() {
+        "bool" => matches!(val, Attribute::Bool(_) | Attribute::Integer(_)),
+        "String" | "RString" | "Template" | "PathBuf" => matches!(val, Attribute::String(_)),
+        "i64" | "i32" | "u64" | "u32" | "usize" | "isize" => {
+            matches!(val, Attribute::Integer(_) | Attribute::Bool(_))
+        }
+        "f64" | "f32" => matches!(
+            val,
+            Attribute::Float(_) | Attribute::Integer(_) | Attribute::Bool(_)
+        ),
+        "Date" => matches!(val, Attribute::Date(_)),
+        "Time" => matches!(val, Attribute::Time(_)),
+        "DateTime" => matches!(val, Attribute::DateTime(_) | Attribute::Date(_)),
+        "AttrMap" => matches!(val, Attribute::Table(_)),
+        t if t.starts_with("Vec<") || t.starts_with("RVec<") => {
+            matches!(val, Attribute::Array(_))
+        }
+        _ => true,
+    }
+}
@@ -1,9 +1,109 @@
+use super::{palette, template};
+use crate::help::FuncType;
 use core::ops::Range;
-use iced::Color;
 use iced::Font;
+use iced::font::{Style, Weight};
+use iced::highlighter;
 use iced_core::text::highlighter::{Format, Highlighter};
-use nadi_core::parser::tokenizer::{TaskToken, get_tokens};
-use std::collections::HashMap;
+use nadi_core::graphics::node::{
+    LINE_COLOR, LINE_WIDTH, NODE_COLOR, NODE_SHAPE, NODE_SIZE, TEXT_COLOR,
+};
+use nadi_core::parser::tokenizer::{TaskToken, Token, get_tokens};
+use nadi_core::tasks::TaskKeyword;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Attributes used to draw the network
+static VISUAL_ATTRS: [&str; 7] = [
+    NODE_COLOR.0,
+    LINE_COLOR.0,
+    TEXT_COLOR.0,
+    LINE_WIDTH.0,
+    NODE_SIZE.0,
+    NODE_SHAPE.0,
+    "nodelabel",
+];
+
+/// Attributes set on the nodes by nadi itself
+static BUILTIN_ATTRS: [&str; 4] = ["INDEX", "NAME", "LEVEL", "ORDER"];
+
+static NAMES_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// Functions and attributes known in the session, to mark the unknown
+/// functions in the tasks
+#[derive(Debug)]
+pub struct Names {
+    /// compared instead of the names, as they are only created when
+    /// the functions or the network change
+    generation: usize,
+    env: HashSet<String>,
+    node: HashSet<String>,
+    network: HashSet<String>,
+    env_vars: HashSet<String>,
+    node_attrs: HashSet<String>,
+    network_attrs: HashSet<String>,
+    /// functions with template arguments
+    templated: HashSet<String>,
+}
+
+impl PartialEq for Names {
+    fn eq(&self, other: &Self) -> bool {
+        self.generation == other.generation
+    }
+}
+
+impl Names {
+    pub fn new(
+        functions: [Vec<String>; 3],
+        attrs: [Vec<String>; 3],
+        templated: Vec<String>,
+    ) -> Self {
+        let [env, node, network] = functions.map(HashSet::from_iter);
+        let [env_vars, node_attrs, network_attrs] = attrs.map(HashSet::from_iter);
+        Self {
+            generation: NAMES_GENERATION.fetch_add(1, Ordering::Relaxed),
+            env,
+            node,
+            network,
+            env_vars,
+            node_attrs,
+            network_attrs,
+            templated: HashSet::from_iter(templated),
+        }
+    }
+
+    /// Whether the functions and attributes are the same, regardless
+    /// of the generation
+    pub fn same_names(&self, other: &Self) -> bool {
+        self.env == other.env
+            && self.node == other.node
+            && self.network == other.network
+            && self.env_vars == other.env_vars
+            && self.node_attrs == other.node_attrs
+            && self.network_attrs == other.network_attrs
+            && self.templated == other.templated
+    }
+
+    /// Node and network tasks can also call the env functions
+    fn has_function(&self, ty: &FuncType, name: &str) -> bool {
+        let funcs = match ty {
+            FuncType::Env => &self.env,
+            FuncType::Node => &self.node,
+            FuncType::Network => &self.network,
+        };
+        funcs.contains(name) || self.env.contains(name)
+    }
+
+    fn has_attr(&self, ty: &FuncType, name: &str) -> bool {
+        match ty {
+            FuncType::Env => &self.env_vars,
+            FuncType::Node => &self.node_attrs,
+            FuncType::Network => &self.network_attrs,
+        }
+        .contains(name)
+    }
+}
 
 struct HlTokens {
     offset: usize,
@@ -31,11 +131,37 @@ impl std::str::FromStr for NadiFileType {
     }
 }
 
-// pub struct Settings {
-//     pub(super) theme: iced::highlighter::Theme,
-//     pub(super) nft: NadiFileType,
-// }
+#[derive(Clone, PartialEq)]
+pub struct Settings {
+    pub nft: NadiFileType,
+    /// lines with the (byte) ranges of the errors to highlight
+    pub errors: Vec<(usize, Range<usize>)>,
+    /// theme of the editor to take the colors from, the light/dark
+    /// colors of the iced theme are used if None
+    pub theme: Option<highlighter::Theme>,
+    /// known functions and attributes for the semantic highlights
+    pub names: Option<Arc<Names>>,
+    /// lines with the (byte) ranges of the matching brackets at the
+    /// cursor
+    pub brackets: Vec<(usize, Range<usize>)>,
+    /// color the brackets by their depth
+    pub rainbow: bool,
+}
+
+impl From<NadiFileType> for Settings {
+    fn from(nft: NadiFileType) -> Self {
+        Self {
+            nft,
+            errors: vec![],
+            theme: None,
+            names: None,
+            brackets: vec![],
+            rainbow: false,
+        }
+    }
+}
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Highlight {
     Comment,
     Keyword,
@@ -43,15 +169,89 @@ pub enum Highlight {
     Paren,
     Variable,
     Function,
+    /// attribute that exists in the network
+    Attribute,
+    /// attribute used to draw the network
+    Visual,
+    /// attribute set by nadi (e.g. `INDEX`)
+    Builtin,
     Bool,
     Number,
     DateTime,
     String,
     Error,
+    /// bracket matching the one at the cursor
+    Bracket,
+    /// bracket in the rainbow mode, with its depth
+    Rainbow(usize),
     None,
 }
 
+/// Highlight along with the editor theme it is drawn in
+#[derive(Clone, Copy, Debug)]
+pub struct Styled {
+    pub hl: Highlight,
+    pub theme: Option<highlighter::Theme>,
+}
+
+impl Styled {
+    pub fn to_format(&self, theme: &iced::Theme) -> Format<Font> {
+        self.hl.format_with(self.theme, theme)
+    }
+}
+
+/// Highlights the colors of the brackets in rainbow mode cycle through
+static RAINBOW: [Highlight; 5] = [
+    Highlight::Paren,
+    Highlight::Keyword,
+    Highlight::Variable,
+    Highlight::Function,
+    Highlight::String,
+];
+
 impl Highlight {
+    /// All the highlights that have a style
+    pub const ALL: [Self; 15] = [
+        Self::Comment,
+        Self::Keyword,
+        Self::Symbol,
+        Self::Paren,
+        Self::Variable,
+        Self::Function,
+        Self::Attribute,
+        Self::Visual,
+        Self::Builtin,
+        Self::Bool,
+        Self::Number,
+        Self::DateTime,
+        Self::String,
+        Self::Error,
+        Self::Bracket,
+    ];
+
+    /// Name used in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Comment => "comment",
+            Self::Keyword => "keyword",
+            Self::Symbol => "symbol",
+            Self::Paren => "paren",
+            Self::Variable => "variable",
+            Self::Function => "function",
+            Self::Attribute => "attribute",
+            Self::Visual => "visual",
+            Self::Builtin => "builtin",
+            Self::Bool => "bool",
+            Self::Number => "number",
+            Self::DateTime => "datetime",
+            Self::String => "string",
+            Self::Error => "error",
+            Self::Bracket => "bracket",
+            Self::Rainbow(_) => "paren",
+            Self::None => "none",
+        }
+    }
+
     fn from_token(tk: &TaskToken, ntf: &NadiFileType) -> Self {
         match ntf {
             NadiFileType::Network => match tk {
@@ -119,62 +319,188 @@ impl Highlight {
         }
     }
 
-    pub fn to_format(&self, _theme: &iced::Theme) -> Format<Font> {
-        let color = match self {
-            Self::Comment => Some(Color::new(0.5, 0.5, 0.5, 0.7)),
-            Self::Keyword => Some(Color::new(0.7, 0.0, 0.0, 1.0)),
-            Self::Symbol => None,
-            Self::Paren => Some(Color::new(0.0, 0.0, 1.0, 1.0)),
-            Self::Variable => Some(Color::new(0.0, 0.5, 0.0, 1.0)),
-            Self::Function => Some(Color::new(0.5, 0.2, 0.2, 1.0)),
-            Self::Bool => Some(Color::new(0.4, 0.6, 0.9, 1.0)),
-            Self::Number => None,
-            Self::DateTime => Some(Color::new(0.1, 0.7, 0.5, 1.0)),
-            Self::String => Some(Color::new(0.1, 0.7, 0.5, 1.0)),
-            Self::Error => Some(Color::new(1.0, 0.3, 0.3, 1.0)),
-            Self::None => None,
+    /// Format with the colors for the light/dark iced theme
+    pub fn to_format(&self, theme: &iced::Theme) -> Format<Font> {
+        self.format_with(None, theme)
+    }
+
+    /// Format with the colors of the editor theme if given, or the
+    /// ones for the light/dark iced theme
+    pub fn format_with(
+        &self,
+        hl_theme: Option<highlighter::Theme>,
+        theme: &iced::Theme,
+    ) -> Format<Font> {
+        let dark = theme.extended_palette().is_dark;
+        let style = match self {
+            // only the colors of the highlights are used
+            Self::Rainbow(depth) => palette::HlStyle {
+                color: palette::style(&RAINBOW[depth % RAINBOW.len()], hl_theme, dark).color,
+                ..Default::default()
+            },
+            hl => palette::style(hl, hl_theme, dark),
         };
-        Format { color, font: None }
+        let font = (style.bold || style.italic).then_some(Font {
+            weight: if style.bold {
+                Weight::Bold
+            } else {
+                Weight::Normal
+            },
+            style: if style.italic {
+                Style::Italic
+            } else {
+                Style::Normal
+            },
+            ..Font::MONOSPACE
+        });
+        Format {
+            color: style.color,
+            font,
+        }
+    }
+}
+
+/// Highlights (with their lengths) of the tokens in a line of tasks,
+/// the attributes of the task are highlighted based on what they
+/// are, the functions not in the names are marked as errors, and the
+/// strings used as templates have their template syntax highlighted
+fn semantic_highlights(
+    tokens: &[Token],
+    nft: &NadiFileType,
+    names: Option<&Names>,
+) -> Vec<(Highlight, usize)> {
+    // keyword of the task in the line
+    let mut task: Option<FuncType> = None;
+    // the tokens are before the function call or the assignment
+    let mut attrs = false;
+    // depth of the parenthesis with the condition after the keyword
+    let mut cond = 0;
+    // strings are templates: in the calls to `render` or functions
+    // with template arguments, or assigned to `nodelabel`
+    let mut templ = false;
+    let mut nodelabel = false;
+    // `<` after the keyword is a bracket, the others are comparisons
+    let mut angle = false;
+    let mut prev: Option<&TaskToken> = None;
+    let mut before_prev: Option<&TaskToken> = None;
+    let mut highlights = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let mut hl = Highlight::from_token(&token.ty, nft);
+        let after_keyword = prev.is_some_and(|p| keyword_type(p).is_some());
+        match &token.ty {
+            TaskToken::WhiteSpace => {
+                highlights.push((hl, token.content.len()));
+                continue;
+            }
+            ty if keyword_type(ty).is_some() => {
+                task = keyword_type(ty);
+                attrs = true;
+                cond = 0;
+                templ = false;
+                nodelabel = false;
+            }
+            TaskToken::AngleStart if after_keyword => angle = true,
+            TaskToken::AngleEnd if angle => angle = false,
+            TaskToken::AngleStart | TaskToken::AngleEnd => hl = Highlight::Symbol,
+            TaskToken::ParenStart if attrs && (after_keyword || cond > 0) => cond += 1,
+            TaskToken::ParenEnd if cond > 0 => cond -= 1,
+            TaskToken::Assignment if cond == 0 && attrs => {
+                attrs = false;
+                templ = nodelabel;
+            }
+            TaskToken::String(value) if templ => {
+                let ty = task.as_ref().unwrap_or(&FuncType::Node);
+                highlights.extend(template::highlights(token.content, value, ty, names));
+                before_prev = prev;
+                prev = Some(&token.ty);
+                continue;
+            }
+            TaskToken::Function => {
+                attrs = false;
+                let func = token.content.rsplit('.').next().unwrap_or_default();
+                templ =
+                    func == "render" || names.is_some_and(|n| n.templated.contains(token.content));
+                if let (Some(names), Some(ty)) = (names, &task)
+                    && !names.has_function(ty, token.content)
+                {
+                    hl = Highlight::Error;
+                }
+            }
+            TaskToken::Variable if attrs => {
+                // `node.attr`, `node<..>.attr` or the condition in
+                // `node(attr)`
+                let dotted = prev == Some(&TaskToken::Dot)
+                    && before_prev
+                        .is_some_and(|p| keyword_type(p).is_some() || *p == TaskToken::AngleEnd);
+                if let (true, Some(ty)) = (dotted || cond > 0, &task) {
+                    hl = attr_highlight(ty, token.content, names);
+                }
+                nodelabel = dotted && token.content == "nodelabel";
+            }
+            _ => (),
+        }
+        highlights.push((hl, token.content.len()));
+        before_prev = prev;
+        prev = Some(&token.ty);
+    }
+    highlights
+}
+
+fn keyword_type(ty: &TaskToken) -> Option<FuncType> {
+    match ty {
+        TaskToken::Keyword(TaskKeyword::Node) => Some(FuncType::Node),
+        TaskToken::Keyword(TaskKeyword::Network) => Some(FuncType::Network),
+        TaskToken::Keyword(TaskKeyword::Env) => Some(FuncType::Env),
+        _ => None,
+    }
+}
+
+pub(super) fn attr_highlight(ty: &FuncType, name: &str, names: Option<&Names>) -> Highlight {
+    if *ty != FuncType::Env && BUILTIN_ATTRS.contains(&name) {
+        Highlight::Builtin
+    } else if *ty != FuncType::Env && VISUAL_ATTRS.contains(&name) {
+        Highlight::Visual
+    } else if names.is_some_and(|n| n.has_attr(ty, name)) {
+        Highlight::Attribute
+    } else {
+        Highlight::Variable
     }
 }
 
 impl HlTokens {
-    fn new(line: &str, nft: &NadiFileType) -> (Option<MultiLineStr>, Self) {
+    fn new(line: &str, nft: &NadiFileType, names: Option<&Names>) -> (Option<MultiLineStr>, Self) {
         let mut mls = None;
         let tk = match get_tokens(line) {
             Ok(tk) => {
-                let tokens = if let Some(p) = tk.iter().position(|t| t.ty == TaskToken::Quote) {
-                    mls = Some(MultiLineStr::Open);
-                    let mut tokens = vec![(
-                        Highlight::String,
-                        tk[p..].iter().map(|t| t.content.len()).sum(),
-                    )];
-                    tokens.extend(
-                        tk[..p]
-                            .iter()
-                            .rev()
-                            .map(|t| (Highlight::from_token(&t.ty, nft), t.content.len())),
-                    );
-                    tokens
-                } else {
-                    tk.iter()
-                        .rev()
+                let quote = tk.iter().position(|t| t.ty == TaskToken::Quote);
+                let code = &tk[..quote.unwrap_or(tk.len())];
+                let highlights = match nft {
+                    NadiFileType::Tasks | NadiFileType::Terminal => {
+                        semantic_highlights(code, nft, names)
+                    }
+                    _ => code
+                        .iter()
                         .map(|t| (Highlight::from_token(&t.ty, nft), t.content.len()))
-                        .collect()
+                        .collect(),
                 };
+                let mut tokens: Vec<(Highlight, usize)> = match quote {
+                    Some(p) => {
+                        mls = Some(MultiLineStr::Open);
+                        vec![(
+                            Highlight::String,
+                            tk[p..].iter().map(|t| t.content.len()).sum(),
+                        )]
+                    }
+                    None => vec![],
+                };
+                tokens.extend(highlights.into_iter().rev());
                 Self { offset: 0, tokens }
             }
-            // for now whole line is shown as error, showing the error
-            // with exact position can be done later
+            // the exact position of the error is highlighted from the
+            // errors in the settings
             Err(_) => Self {
                 offset: 0,
-                tokens: vec![(
-                    match nft {
-                        NadiFileType::Terminal => Highlight::None,
-                        _ => Highlight::Error,
-                    },
-                    line.len(),
-                )],
+                tokens: vec![(Highlight::None, line.len())],
             },
         };
         (mls, tk)
@@ -265,38 +591,123 @@ enum MultiLineStr {
 pub struct NadiHighlighter {
     curr_line: usize,
     ml_str: HashMap<usize, MultiLineStr>,
-    settings: NadiFileType,
+    /// depth of the brackets at the start of the lines for rainbow
+    depth: HashMap<usize, usize>,
+    settings: Settings,
+}
+
+/// Highlights with the ranges overlaid on them
+fn overlaid(
+    tokens: impl Iterator<Item = (Range<usize>, Highlight)>,
+    ranges: &[Range<usize>],
+    overlay: Highlight,
+) -> Vec<(Range<usize>, Highlight)> {
+    let mut hl = vec![];
+    for (range, h) in tokens {
+        let mut start = range.start;
+        for err in ranges {
+            let (es, ee) = (err.start.max(start), err.end.min(range.end));
+            if es >= ee {
+                continue;
+            }
+            if start < es {
+                hl.push((start..es, h));
+            }
+            hl.push((es..ee, overlay));
+            start = ee;
+        }
+        if start < range.end {
+            hl.push((start..range.end, h));
+        }
+    }
+    hl
+}
+
+/// Brackets colored by their depth, starting from the depth at the
+/// start of the line
+fn rainbow(
+    line: &str,
+    tokens: impl Iterator<Item = (Range<usize>, Highlight)>,
+    depth: &mut usize,
+) -> Vec<(Range<usize>, Highlight)> {
+    let mut hl = vec![];
+    for (range, h) in tokens {
+        if h != Highlight::Paren {
+            hl.push((range, h));
+            continue;
+        }
+        for (i, c) in line[range.clone()].char_indices() {
+            let d = match c {
+                '(' | '[' | '{' | '<' => {
+                    *depth += 1;
+                    *depth - 1
+                }
+                ')' | ']' | '}' | '>' => {
+                    *depth = depth.saturating_sub(1);
+                    *depth
+                }
+                _ => *depth,
+            };
+            let st = range.start + i;
+            hl.push((st..(st + c.len_utf8()), Highlight::Rainbow(d)));
+        }
+    }
+    hl
 }
 
 impl Highlighter for NadiHighlighter {
-    type Settings = NadiFileType;
-    type Highlight = Highlight;
+    type Settings = Settings;
+    type Highlight = Styled;
     type Iterator<'a> = Box<dyn Iterator<Item = (Range<usize>, Self::Highlight)> + 'a>;
     fn new(settings: &Self::Settings) -> Self {
         Self {
             curr_line: 0,
             ml_str: HashMap::new(),
+            depth: HashMap::new(),
             settings: settings.clone(),
         }
     }
     fn update(&mut self, new_settings: &Self::Settings) {
-        self.settings = new_settings.clone();
+        let old = std::mem::replace(&mut self.settings, new_settings.clone());
+        // moving the cursor only changes the matching brackets, so
+        // the highlights before their lines are kept
+        let brackets_only = Settings {
+            brackets: new_settings.brackets.clone(),
+            ..old.clone()
+        } == *new_settings;
+        let line = if brackets_only {
+            old.brackets
+                .iter()
+                .chain(&new_settings.brackets)
+                .map(|(l, _)| *l)
+                .min()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        self.change_line(line);
     }
     fn change_line(&mut self, line: usize) {
         self.curr_line = line;
         // if line is changed, remove the saved states for
         // MultiLineStrings for all lines after this
         self.ml_str.retain(|l, _| l <= &line);
+        self.depth.retain(|l, _| l <= &line);
     }
     fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
-        if self.settings == NadiFileType::Terminal {
-            return Box::new(HlTokens::new(line, &self.settings).1);
+        let nft = &self.settings.nft;
+        let theme = self.settings.theme;
+        let styled = move |(r, hl)| (r, Styled { hl, theme });
+        if nft == &NadiFileType::Terminal {
+            return Box::new(HlTokens::new(line, nft, None).1.map(styled));
         }
 
         let (mls, tk) = match self.ml_str.get(&self.curr_line) {
-            None | Some(MultiLineStr::Open) => HlTokens::new(line, &self.settings),
+            None | Some(MultiLineStr::Open) => {
+                HlTokens::new(line, nft, self.settings.names.as_deref())
+            }
             Some(MultiLineStr::In) | Some(MultiLineStr::Close) | Some(MultiLineStr::CloseOpen) => {
-                HlTokens::in_quote(line, &self.settings)
+                HlTokens::in_quote(line, nft)
             }
         };
         if let Some(mls) = mls {
@@ -310,8 +721,31 @@ impl Highlighter for NadiHighlighter {
         } else {
             self.ml_str.remove(&self.curr_line);
         }
+        let on_line = |ranges: &[(usize, Range<usize>)]| -> Vec<Range<usize>> {
+            ranges
+                .iter()
+                .filter(|(l, _)| *l == self.curr_line)
+                .map(|(_, r)| r.clone())
+                .collect()
+        };
+        let errors = on_line(&self.settings.errors);
+        let brackets = on_line(&self.settings.brackets);
+        let mut hl: Vec<(Range<usize>, Highlight)> = if self.settings.rainbow {
+            let mut depth = self.depth.get(&self.curr_line).copied().unwrap_or(0);
+            let hl = rainbow(line, tk, &mut depth);
+            self.depth.insert(self.curr_line + 1, depth);
+            hl
+        } else {
+            tk.collect()
+        };
+        if !brackets.is_empty() {
+            hl = overlaid(hl.into_iter(), &brackets, Highlight::Bracket);
+        }
+        if !errors.is_empty() {
+            hl = overlaid(hl.into_iter(), &errors, Highlight::Error);
+        }
         self.curr_line += 1;
-        Box::new(tk)
+        Box::new(hl.into_iter().map(styled))
     }
     fn current_line(&self) -> usize {
         self.curr_line
@@ -0,0 +1,104 @@
+//! Outline of the tasks file: the tasks, and the comments on their
+//! own lines that act as the section headers.
+use super::lint::task_tokens;
+use nadi_core::parser::tasks;
+use nadi_core::parser::tokenizer::{self, TaskToken};
+use nadi_core::tasks::{Task, TaskInput, TaskType};
+use std::ops::Range;
+
+#[derive(Clone, Debug)]
+pub enum OutlineKind {
+    Section,
+    Task(TaskType),
+}
+
+#[derive(Clone, Debug)]
+pub struct OutlineItem {
+    pub kind: OutlineKind,
+    /// section title, or the function/attribute of the task
+    pub name: String,
+    pub lines: Range<usize>,
+}
+
+/// Outline of the text, None if it can't be parsed
+pub fn outline(text: &str) -> Option<Vec<OutlineItem>> {
+    let tokens = tokenizer::get_tokens(text).ok()?;
+    let lines = task_lines(text)?;
+    let mut items = sections(&tokens);
+    let tasks = tasks::parse(tokens).ok()?;
+    for (task, lines) in tasks.into_iter().zip(lines) {
+        items.push(OutlineItem {
+            name: task_name(&task),
+            kind: OutlineKind::Task(task.ty),
+            lines,
+        });
+    }
+    items.sort_by_key(|i| i.lines.start);
+    Some(items)
+}
+
+/// Lines of the tasks, in the same order as the parsed tasks
+pub fn task_lines(text: &str) -> Option<Vec<Range<usize>>> {
+    let tokens = tokenizer::get_tokens(text).ok()?;
+    Some(
+        task_tokens(&tokens)
+            .into_iter()
+            .map(|t| t.keyword.line..(t.last_line + 1))
+            .collect(),
+    )
+}
+
+/// Function called in the task, and the attribute it is assigned to
+fn task_name(task: &Task) -> String {
+    let call = match &task.input {
+        TaskInput::Function(f) => Some(format!("{}()", f.name)),
+        _ => None,
+    };
+    match (&task.attribute, call) {
+        (Some(a), Some(c)) => format!("{a} = {c}"),
+        (Some(a), None) => a.clone(),
+        (None, Some(c)) => c,
+        (None, None) => String::new(),
+    }
+}
+
+/// Comments on their own lines, only the first non-empty line of a
+/// comment block is used as the title
+fn sections(tokens: &[tokenizer::Token]) -> Vec<OutlineItem> {
+    let mut items = vec![];
+    let mut line = 0;
+    // only whitespace so far in the current line
+    let mut line_start = true;
+    // current line is a comment, and the comment block has a title
+    let mut comment = false;
+    let mut titled = false;
+    for token in tokens {
+        match token.ty {
+            TaskToken::NewLine => {
+                line += 1;
+                line_start = true;
+                titled &= comment;
+                comment = false;
+            }
+            TaskToken::WhiteSpace => (),
+            TaskToken::Comment if line_start => {
+                comment = true;
+                let title = token.content.trim_start_matches('#').trim();
+                if !titled && !title.is_empty() {
+                    titled = true;
+                    items.push(OutlineItem {
+                        kind: OutlineKind::Section,
+                        name: title.to_string(),
+                        lines: line..(line + 1),
+                    });
+                }
+            }
+            _ => {
+                line_start = false;
+                // strings can span multiple lines
+                line += token.content.matches('\n').count();
+            }
+        }
+    }
+    items
+}
@@ -0,0 +1,315 @@
+//! Colors for the highlights of the nadi files.
+//!
+//! There is a palette for the light and dark themes, and one for each
+//! of the themes of the editor highlighter. The styles can be changed
+//! in `highlight.toml` in the nadi config directory, with a table per
+//! palette (or `all` for every palette) like:
+//!
+//! ```toml
+//! [dark]
+//! keyword = "#c678dd"
+//! comment = {color = "#7f848e", italic = true}
+//! ```
+use super::my_hl::Highlight;
+use iced::highlighter;
+use iced::{Color, color};
+use nadi_core::attrs::{AttrMap, Attribute};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct HlStyle {
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+impl HlStyle {
+    const fn color(color: Color) -> Self {
+        Self {
+            color: Some(color),
+            bold: false,
+            italic: false,
+        }
+    }
+
+    const fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+
+    const fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+}
+
+type Palette = HashMap<Highlight, HlStyle>;
+
+static PALETTES: LazyLock<HashMap<&'static str, Palette>> = LazyLock::new(|| {
+    let mut palettes: HashMap<&str, Palette> = PALETTE_NAMES
+        .iter()
+        .map(|name| (*name, default_palette(name)))
+        .collect();
+    if let Some(config) = load_config() {
+        for (name, palette) in palettes.iter_mut() {
+            for table in ["all", name] {
+                if let Some(Attribute::Table(styles)) = config.get(table) {
+                    apply_overrides(palette, styles, table);
+                }
+            }
+        }
+    }
+    palettes
+});
+
+static PALETTE_NAMES: [&str; 7] = [
+    "light",
+    "dark",
+    "solarized_dark",
+    "base16_mocha",
+    "base16_ocean",
+    "base16_eighties",
+    "inspired_github",
+];
+
+fn palette_name(theme: Option<highlighter::Theme>, dark: bool) -> &'static str {
+    match theme {
+        Some(highlighter::Theme::SolarizedDark) => "solarized_dark",
+        Some(highlighter::Theme::Base16Mocha) => "base16_mocha",
+        Some(highlighter::Theme::Base16Ocean) => "base16_ocean",
+        Some(highlighter::Theme::Base16Eighties) => "base16_eighties",
+        Some(highlighter::Theme::InspiredGitHub) => "inspired_github",
+        None if dark => "dark",
+        None => "light",
+    }
+}
+
+/// Style of the highlight in the palette of the highlighter theme, or
+/// the light/dark one if there is no highlighter theme
+pub fn style(hl: &Highlight, theme: Option<highlighter::Theme>, dark: bool) -> HlStyle {
+    PALETTES
+        .get(palette_name(theme, dark))
+        .and_then(|p| p.get(hl))
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Colors of the base16 themes in the order: comment, red, orange,
+/// yellow, green, cyan, blue, magenta
+fn base16(colors: [Color; 8]) -> Palette {
+    let [comment, red, orange, yellow, green, cyan, blue, magenta] = colors;
+    palette([
+        (Highlight::Comment, HlStyle::color(comment).italic()),
+        (Highlight::Keyword, HlStyle::color(magenta)),
+        (Highlight::Symbol, HlStyle::default()),
+        (Highlight::Paren, HlStyle::color(cyan)),
+        (Highlight::Variable, HlStyle::color(yellow)),
+        (Highlight::Function, HlStyle::color(blue)),
+        (Highlight::Attribute, HlStyle::color(red)),
+        (Highlight::Visual, HlStyle::color(magenta).italic()),
+        (Highlight::Builtin, HlStyle::color(orange).bold()),
+        (Highlight::Bool, HlStyle::color(orange)),
+        (Highlight::Number, HlStyle::color(orange)),
+        (Highlight::DateTime, HlStyle::color(cyan)),
+        (Highlight::String, HlStyle::color(green)),
+        (Highlight::Error, HlStyle::color(red).bold()),
+        (Highlight::Bracket, HlStyle::color(yellow).bold()),
+    ])
+}
+
+/// Palette from the styles of the highlights, the ones not given
+/// have the default style
+fn palette<const N: usize>(styles: [(Highlight, HlStyle); N]) -> Palette {
+    styles.into_iter().collect()
+}
+
+fn default_palette(name: &str) -> Palette {
+    match name {
+        // the colors used before there were palettes
+        "light" => palette([
+            (
+                Highlight::Comment,
+                HlStyle::color(Color::new(0.5, 0.5, 0.5, 0.7)),
+            ),
+            (
+                Highlight::Keyword,
+                HlStyle::color(Color::new(0.7, 0.0, 0.0, 1.0)),
+            ),
+            (Highlight::Symbol, HlStyle::default()),
+            (
+                Highlight::Paren,
+                HlStyle::color(Color::new(0.0, 0.0, 1.0, 1.0)),
+            ),
+            (
+                Highlight::Variable,
+                HlStyle::color(Color::new(0.0, 0.5, 0.0, 1.0)),
+            ),
+            (
+                Highlight::Function,
+                HlStyle::color(Color::new(0.5, 0.2, 0.2, 1.0)),
+            ),
+            (
+                Highlight::Attribute,
+                HlStyle::color(Color::new(0.0, 0.4, 0.6, 1.0)),
+            ),
+            (
+                Highlight::Visual,
+                HlStyle::color(Color::new(0.6, 0.0, 0.6, 1.0)).italic(),
+            ),
+            (
+                Highlight::Builtin,
+                HlStyle::color(Color::new(0.6, 0.4, 0.0, 1.0)).bold(),
+            ),
+            (
+                Highlight::Bool,
+                HlStyle::color(Color::new(0.4, 0.6, 0.9, 1.0)),
+            ),
+            (Highlight::Number, HlStyle::default()),
+            (
+                Highlight::DateTime,
+                HlStyle::color(Color::new(0.1, 0.7, 0.5, 1.0)),
+            ),
+            (
+                Highlight::String,
+                HlStyle::color(Color::new(0.1, 0.7, 0.5, 1.0)),
+            ),
+            (
+                Highlight::Error,
+                HlStyle::color(Color::new(1.0, 0.3, 0.3, 1.0)),
+            ),
+            (
+                Highlight::Bracket,
+                HlStyle::color(Color::new(0.9, 0.4, 0.0, 1.0)).bold(),
+            ),
+        ]),
+        "dark" => palette([
+            (
+                Highlight::Comment,
+                HlStyle::color(color!(0x7f848e)).italic(),
+            ),
+            (Highlight::Keyword, HlStyle::color(color!(0xc678dd))),
+            (Highlight::Symbol, HlStyle::default()),
+            (Highlight::Paren, HlStyle::color(color!(0x56b6c2))),
+            (Highlight::Variable, HlStyle::color(color!(0xe5c07b))),
+            (Highlight::Function, HlStyle::color(color!(0x61afef))),
+            (Highlight::Attribute, HlStyle::color(color!(0xe06c75))),
+            (Highlight::Visual, HlStyle::color(color!(0xc678dd)).italic()),
+            (Highlight::Builtin, HlStyle::color(color!(0xd19a66)).bold()),
+            (Highlight::Bool, HlStyle::color(color!(0xd19a66))),
+            (Highlight::Number, HlStyle::color(color!(0xd19a66))),
+            (Highlight::DateTime, HlStyle::color(color!(0x56b6c2))),
+            (Highlight::String, HlStyle::color(color!(0x98c379))),
+            (Highlight::Error, HlStyle::color(color!(0xff5555)).bold()),
+            (Highlight::Bracket, HlStyle::color(color!(0xffcc00)).bold()),
+        ]),
+        "solarized_dark" => base16([
+            color!(0x586e75),
+            color!(0xdc322f),
+            color!(0xcb4b16),
+            color!(0xb58900),
+            color!(0x859900),
+            color!(0x2aa198),
+            color!(0x268bd2),
+            color!(0x6c71c4),
+        ]),
+        "base16_mocha" => base16([
+            color!(0x7e705a),
+            color!(0xcb6077),
+            color!(0xd28b71),
+            color!(0xf4bc87),
+            color!(0xbeb55b),
+            color!(0x7bbda4),
+            color!(0x8ab3b5),
+            color!(0xa89bb9),
+        ]),
+        "base16_ocean" => base16([
+            color!(0x65737e),
+            color!(0xbf616a),
+            color!(0xd08770),
+            color!(0xebcb8b),
+            color!(0xa3be8c),
+            color!(0x96b5b4),
+            color!(0x8fa1b3),
+            color!(0xb48ead),
+        ]),
+        "base16_eighties" => base16([
+            color!(0x747369),
+            color!(0xf2777a),
+            color!(0xf99157),
+            color!(0xffcc66),
+            color!(0x99cc99),
+            color!(0x66cccc),
+            color!(0x6699cc),
+            color!(0xcc99cc),
+        ]),
+        "inspired_github" => palette([
+            (
+                Highlight::Comment,
+                HlStyle::color(color!(0x969896)).italic(),
+            ),
+            (Highlight::Keyword, HlStyle::color(color!(0xa71d5d)).bold()),
+            (Highlight::Symbol, HlStyle::default()),
+            (Highlight::Paren, HlStyle::color(color!(0x0086b3))),
+            (Highlight::Variable, HlStyle::color(color!(0xed6a43))),
+            (Highlight::Function, HlStyle::color(color!(0x795da3))),
+            (Highlight::Attribute, HlStyle::color(color!(0x63a35c))),
+            (Highlight::Visual, HlStyle::color(color!(0xa71d5d)).italic()),
+            (Highlight::Builtin, HlStyle::color(color!(0xed6a43)).bold()),
+            (Highlight::Bool, HlStyle::color(color!(0x0086b3))),
+            (Highlight::Number, HlStyle::color(color!(0x0086b3))),
+            (Highlight::DateTime, HlStyle::color(color!(0x183691))),
+            (Highlight::String, HlStyle::color(color!(0x183691))),
+            (Highlight::Error, HlStyle::color(color!(0xb52a1d)).bold()),
+            (Highlight::Bracket, HlStyle::color(color!(0xe36209)).bold()),
+        ]),
+        _ => Palette::new(),
+    }
+}
+
+/// Load `highlight.toml` from the config directory, errors are
+/// printed and the default palettes are used
+fn load_config() -> Option<AttrMap> {
+    let path = dirs::config_dir()?.join("nadi").join("highlight.toml");
+    let txt = std::fs::read_to_string(&path).ok()?;
+    let parsed = nadi_core::parser::tokenizer::get_tokens(&txt)
+        .map_err(|e| e.to_string())
+        .and_then(|tk| nadi_core::parser::attrs::parse(tk).map_err(|e| e.to_string()));
+    match parsed {
+        Ok(config) => Some(config),
+        Err(e) => {
+            eprintln!("Error in {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+/// Styles are either the color, or a table with the color, bold and
+/// italic; the ones not given (or invalid) are kept from the palette
+fn apply_overrides(palette: &mut Palette, styles: &AttrMap, table: &str) {
+    for hl in Highlight::ALL {
+        let style = palette.entry(hl).or_default();
+        let set_color = |style: &mut HlStyle, c: &str| match Color::parse(c) {
+            Some(color) => style.color = Some(color),
+            None => eprintln!(
+                "Invalid color {c:?} for {} in [{table}] of highlight.toml",
+                hl.name()
+            ),
+        };
+        match styles.get(hl.name()) {
+            Some(Attribute::String(c)) => set_color(style, c),
+            Some(Attribute::Table(t)) => {
+                if let Some(Attribute::String(c)) = t.get("color") {
+                    set_color(style, c);
+                }
+                if let Some(Attribute::Bool(b)) = t.get("bold") {
+                    style.bold = *b;
+                }
+                if let Some(Attribute::Bool(i)) = t.get("italic") {
+                    style.italic = *i;
+                }
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,331 @@
+//! Highlights for the string templates: the `{attr}` placeholders
+//! with their `:transformers` and `?` fallbacks, and the `$(cmd)`
+//! and `=(lisp)` parts in the strings passed as templates.
+use super::my_hl::{Highlight, Names, attr_highlight};
+use crate::help::FuncType;
+use crate::help::index::base_type;
+use nadi_core::string_template::Template;
+
+/// Whether the argument type is rendered as a template
+pub fn is_template(ty: &str) -> bool {
+    base_type(ty) == "Template"
+}
+
+/// Error in the template, if it can't be parsed
+pub fn error(value: &str) -> Option<String> {
+    // the template parser steps through the bytes, and panics inside
+    // the multi-byte characters
+    if !value.is_ascii() {
+        return Some("Templates can only have ASCII characters".to_string());
+    }
+    Template::parse_template(value).err().map(|e| e.to_string())
+}
+
+/// Highlights of the string token with the template, the `content`
+/// is the token in the source (with quotes and escapes) and the
+/// `value` is the string it is parsed into. The whole string is an
+/// error if the template can't be parsed.
+pub fn highlights(
+    content: &str,
+    value: &str,
+    ty: &FuncType,
+    names: Option<&Names>,
+) -> Vec<(Highlight, usize)> {
+    if error(value).is_some() {
+        return vec![(Highlight::Error, content.len())];
+    }
+    let Some(widths) = source_widths(content, value) else {
+        return vec![(Highlight::String, content.len())];
+    };
+    let chars: Vec<char> = value.chars().collect();
+    let mut hl = vec![Highlight::String; chars.len()];
+    parts(&chars, 0..chars.len(), &mut hl, ty, names);
+
+    let mut tokens: Vec<(Highlight, usize)> = vec![(Highlight::String, 1)];
+    for (h, w) in hl.into_iter().zip(widths) {
+        match tokens.last_mut() {
+            Some((last, l)) if *last == h => *l += w,
+            _ => tokens.push((h, w)),
+        }
+    }
+    match tokens.last_mut() {
+        Some((Highlight::String, l)) => *l += 1,
+        _ => tokens.push((Highlight::String, 1)),
+    }
+    tokens
+}
+
+/// Bytes in the source for each character of the string value, None
+/// if they don't line up (e.g. unicode escapes)
+fn source_widths(content: &str, value: &str) -> Option<Vec<usize>> {
+    let inner = content.strip_prefix('"')?.strip_suffix('"')?;
+    let mut widths = vec![];
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        let w = match c {
+            '\\' => 1 + chars.next()?.len_utf8(),
+            c => c.len_utf8(),
+        };
+        widths.push(w);
+    }
+    (widths.len() == value.chars().count()).then_some(widths)
+}
+
+/// Same as the template tokenizer, but marks the characters
+fn parts(
+    chars: &[char],
+    range: std::ops::Range<usize>,
+    hl: &mut [Highlight],
+    ty: &FuncType,
+    names: Option<&Names>,
+) {
+    let mut i = range.start;
+    while i < range.end {
+        match (chars[i], chars.get(i + 1)) {
+            // escaped characters are literal
+            ('\\', _) => i += 1,
+            ('$', Some('(')) => {
+                let Some(end) = find_end(chars, ')', i + 2, range.end) else {
+                    return;
+                };
+                hl[i..(i + 2)].fill(Highlight::Paren);
+                hl[end] = Highlight::Paren;
+                parts(chars, (i + 2)..end, hl, ty, names);
+                i = end;
+            }
+            ('=', Some('(')) => {
+                let Some(end) = find_end(chars, ')', i + 2, range.end) else {
+                    return;
+                };
+                hl[i] = Highlight::Symbol;
+                hl[(i + 1)..=end].fill(Highlight::Function);
+                i = end;
+            }
+            ('{', _) => {
+                let Some(end) = find_end(chars, '}', i + 1, range.end) else {
+                    return;
+                };
+                hl[i] = Highlight::Paren;
+                hl[end] = Highlight::Paren;
+                placeholder(chars, (i + 1)..end, hl, ty, names);
+                i = end;
+            }
+            ('"', _) => match find_end(chars, '"', i + 1, range.end) {
+                Some(end) => i = end,
+                None => return,
+            },
+            _ => (),
+        }
+        i += 1;
+    }
+}
+
+/// Alternatives separated by `?` inside the braces
+fn placeholder(
+    chars: &[char],
+    range: std::ops::Range<usize>,
+    hl: &mut [Highlight],
+    ty: &FuncType,
+    names: Option<&Names>,
+) {
+    let mut start = range.start;
+    for i in range.clone().chain([range.end]) {
+        if i < range.end && chars[i] != '?' {
+            continue;
+        }
+        if i < range.end {
+            hl[i] = Highlight::Symbol;
+        }
+        // same trim as the template parser
+        let mut s = start;
+        let mut e = i;
+        while s < e && chars[s].is_whitespace() {
+            s += 1;
+        }
+        while e > s && chars[e - 1].is_whitespace() {
+            e -= 1;
+        }
+        alternative(chars, s..e, hl, ty, names);
+        start = i + 1;
+    }
+}
+
+fn alternative(
+    chars: &[char],
+    range: std::ops::Range<usize>,
+    hl: &mut [Highlight],
+    ty: &FuncType,
+    names: Option<&Names>,
+) {
+    if range.is_empty() {
+        return;
+    }
+    match chars[range.start] {
+        // `{{}` and `{}}` are the braces themselves
+        '{' | '}' if range.len() == 1 => (),
+        // literal value
+        '"' => (),
+        '%' => hl[range].fill(Highlight::DateTime),
+        '=' => {
+            hl[range.start] = Highlight::Symbol;
+            hl[(range.start + 1)..range.end].fill(Highlight::Function);
+        }
+        _ => {
+            let var: String = chars[range.clone()]
+                .iter()
+                .take_while(|c| **c != ':')
+                .collect();
+            let var_end = range.start + var.chars().count();
+            hl[range.start..var_end].fill(attr_highlight(ty, &var, names));
+            transformers(chars, var_end..range.end, hl);
+        }
+    }
+}
+
+/// `:name(args)` transformers after the variable
+fn transformers(chars: &[char], range: std::ops::Range<usize>, hl: &mut [Highlight]) {
+    let mut args = false;
+    for i in range {
+        hl[i] = match chars[i] {
+            ':' if !args => Highlight::Symbol,
+            '(' => {
+                args = true;
+                Highlight::Paren
+            }
+            ')' => {
+                args = false;
+                Highlight::Paren
+            }
+            _ if args => Highlight::Number,
+            _ => Highlight::Function,
+        };
+    }
+}
+
+/// Position of the closing character, with the nested pairs skipped
+fn find_end(chars: &[char], end: char, from: usize, until: usize) -> Option<usize> {
+    if end == '"' {
+        return (from..until).find(|&i| chars[i] == '"');
+    }
+    let mut nest: Vec<char> = vec![];
+    for (i, &c) in chars.iter().enumerate().take(until).skip(from) {
+        if c == end && nest.is_empty() {
+            return Some(i);
+        }
+        match c {
+            '"' if nest.contains(&'"') => while nest.pop() != Some('"') {},
+            '{' | '"' | '(' => nest.push(c),
+            '}' | ')' => {
+                nest.pop();
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Highlight as H;
+
+    fn hl(content: &str, value: &str) -> Vec<(Highlight, usize)> {
+        highlights(content, value, &FuncType::Node, None)
+    }
+
+    #[test]
+    fn template_type() {
+        assert!(is_template("Template"));
+        assert!(!is_template("String"));
+    }
+
+    #[test]
+    fn placeholder() {
+        assert_eq!(
+            hl(r#""{x}""#, "{x}"),
+            [
+                (H::String, 1),
+                (H::Paren, 1),
+                (H::Variable, 1),
+                (H::Paren, 1),
+                (H::String, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn transformers() {
+        assert_eq!(
+            hl(r#""a {x:case(up)}""#, "a {x:case(up)}"),
+            [
+                (H::String, 3),
+                (H::Paren, 1),
+                (H::Variable, 1),
+                (H::Symbol, 1),
+                (H::Function, 4),
+                (H::Paren, 1),
+                (H::Number, 2),
+                (H::Paren, 2),
+                (H::String, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn escaped_alternative() {
+        assert_eq!(
+            hl(r#""{a?\"b\"}""#, r#"{a?"b"}"#),
+            [
+                (H::String, 1),
+                (H::Paren, 1),
+                (H::Variable, 1),
+                (H::Symbol, 1),
+                (H::String, 5),
+                (H::Paren, 1),
+                (H::String, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn command() {
+        assert_eq!(
+            hl(r#""$(echo {x})""#, "$(echo {x})"),
+            [
+                (H::String, 1),
+                (H::Paren, 2),
+                (H::String, 5),
+                (H::Paren, 1),
+                (H::Variable, 1),
+                (H::Paren, 2),
+                (H::String, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(error("{x").is_some());
+        assert_eq!(hl(r#""{x""#, "{x"), [(H::Error, 4)]);
+        // the template parser only works on ASCII
+        assert!(error("é {x}").is_some());
+        assert_eq!(hl(r#""é {x}""#, "é {x}"), [(H::Error, 8)]);
+    }
+
+    #[test]
+    fn escapes() {
+        // `\n` is two bytes in the source for a char in the value
+        assert_eq!(
+            hl(r#""\n{x}""#, "\n{x}"),
+            [
+                (H::String, 3),
+                (H::Paren, 1),
+                (H::Variable, 1),
+                (H::Paren, 1),
+                (H::String, 1)
+            ]
+        );
+        // the escape is longer than the char, can't line them up
+        assert_eq!(hl(r#""\u{41}""#, "A"), [(H::String, 8)]);
+    }
+}
@@ -1,14 +1,29 @@
 use crate::icons;
+use iced::keyboard::{self, Key, key::Named};
 use iced::widget::{
-    button, center, column, horizontal_space, markdown, row, scrollable, text, text_input, toggler,
+    button, center, column, horizontal_space, markdown, pick_list, row, scrollable, text,
+    text_input, toggler,
 };
-use iced::{Color, Element, Length, Theme, widget::Column};
-use nadi_core::functions::{FuncArg, NadiFunctions};
+use iced::{Color, Element, Length, Subscription, Task, Theme, widget::Column};
+use nadi_core::functions::{FuncArg, FuncArgType, NadiFunctions};
+use std::ops::Range;
+
+pub mod ansi;
+pub mod examples;
+pub mod favorites;
+pub mod find;
+pub mod index;
+pub mod page;
+pub mod plugins;
+use favorites::Bookmarks;
+use page::{FuncHelp, FuncPage};
+use plugins::PluginInfo;
 
 pub static MAIN_HELP: &str = include_str!("../markdown/main.md");
 static FUNC_WIDTH: f32 = 300.0;
+static OUTLINE_WIDTH: f32 = 200.0;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum FuncType {
     Node,
     Network,
@@ -29,11 +44,45 @@ impl std::fmt::Display for FuncType {
     }
 }
 
+impl std::str::FromStr for FuncType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "node" => Ok(Self::Node),
+            "net" | "network" => Ok(Self::Network),
+            "env" => Ok(Self::Env),
+            _ => Err(format!("Invalid function type {s}, use node/network/env")),
+        }
+    }
+}
+
 pub struct MdHelp {
     pub light_theme: bool,
     functions: NadiFunctions,
+    plugins: Vec<PluginInfo>,
     state: Option<FuncType>,
+    /// show only the favorite functions
+    favorites: bool,
+    bookmarks: Bookmarks,
+    plugin: Option<String>,
+    group_plugins: bool,
     search: String,
+    page: Option<FuncPage>,
+    /// index of functions, shown instead of the page when present
+    index: Option<index::FuncIndex>,
+    /// markdown source for the pages other than functions
+    source: String,
+    outline: Vec<find::Heading>,
+    show_outline: bool,
+    /// query for the find bar, None when it is closed
+    find: Option<String>,
+    find_current: usize,
+    /// positions of the matches in the page (0 to 1)
+    find_positions: Vec<f32>,
+    content_id: scrollable::Id,
+    find_id: text_input::Id,
+    /// Context to run the examples in, loaded on the first run
+    sandbox: Option<examples::Sandbox>,
     markdown: Vec<markdown::Item>,
     collapsed: bool,
     embedded: bool,
@@ -43,22 +92,58 @@ pub struct MdHelp {
 pub enum Message {
     LinkClicked(markdown::Url),
     Home,
+    Index,
+    IndexGroupChange(index::IndexGroup),
+    IndexTypeChange(Option<String>),
     Github,
     Book,
     ToggleCollapsed,
     Function(FuncType, String),
     FuncTypeChange(Option<FuncType>),
+    ShowFavorites,
+    ToggleFavorite(FuncType, String),
+    PluginChange(Option<String>),
+    Plugin(String),
+    GroupPlugins(bool),
+    ReloadPlugins,
     ThemeChange(bool),
     SearchChange(String),
+    RunExample(usize),
+    ToggleOutline,
+    ScrollTo(f32),
+    OpenFind,
+    CloseFind,
+    FindChange(String),
+    FindNext,
+    FindPrevious,
+    // handled in the main window
+    InsertTemplate(FuncType, String),
 }
 
 impl Default for MdHelp {
     fn default() -> Self {
+        let functions = NadiFunctions::new();
         Self {
             light_theme: false,
-            functions: NadiFunctions::new(),
+            plugins: plugins::plugins_info(&functions),
+            functions,
             state: None,
+            favorites: false,
+            bookmarks: Bookmarks::load(),
+            plugin: None,
+            group_plugins: false,
             search: String::new(),
+            page: None,
+            index: None,
+            source: MAIN_HELP.to_string(),
+            outline: find::headings(MAIN_HELP),
+            show_outline: true,
+            find: None,
+            find_current: 0,
+            find_positions: vec![],
+            content_id: scrollable::Id::unique(),
+            find_id: text_input::Id::unique(),
+            sandbox: None,
             markdown: markdown::parse(MAIN_HELP).collect(),
             collapsed: false,
             embedded: false,
@@ -70,7 +155,7 @@ impl Default for MdHelp {
 // traits have same functions
 macro_rules! help {
     ($ty:expr, $name:expr, $func:expr) => {
-        help_to_markdown(
+        FuncHelp::new(
             $ty,
             &$name,
             &$func.args(),
@@ -85,26 +170,58 @@ impl MdHelp {
     pub fn embed(mut self) -> Self {
         self.embedded = true;
         self.collapsed = true;
+        self.show_outline = false;
         self
     }
     pub fn view(&self) -> Element<'_, Message> {
         let mut controls = row![
             button("Home").on_press(Message::Home),
+            button("Index").on_press(Message::Index),
             button("Book").on_press(Message::Book),
             button("GitHub").on_press(Message::Github),
-            horizontal_space()
+            horizontal_space(),
+            button("Outline")
+                .on_press(Message::ToggleOutline)
+                .style(if self.show_outline {
+                    button::success
+                } else {
+                    button::primary
+                }),
+            icons::action(
+                icons::search_icon(),
+                "Find (Ctrl+F)",
+                Some(Message::OpenFind)
+            ),
         ]
         .spacing(20)
-        .padding(10);
-        if !self.embedded {
+        .padding(10)
+        .align_y(iced::Alignment::Center);
+        if self.embedded {
+            if let Some(page) = &self.page {
+                controls = controls.push(button("Insert").on_press(Message::InsertTemplate(
+                    page.help.ty.clone(),
+                    page.help.name.clone(),
+                )));
+            }
+        } else {
             controls = controls.push(toggler(self.light_theme).on_toggle(Message::ThemeChange));
         }
-        let md = markdown::view(
-            &self.markdown,
-            markdown::Settings::default(),
-            md_style(self.light_theme),
-        )
-        .map(Message::LinkClicked);
+        let md = if let Some(index) = &self.index {
+            index.view(&self.state)
+        } else {
+            match &self.page {
+                Some(page) => page.view(
+                    self.light_theme,
+                    self.bookmarks.is_favorite(&page.help.ty, &page.help.name),
+                ),
+                None => markdown::view(
+                    &self.markdown,
+                    markdown::Settings::default(),
+                    md_style(self.light_theme),
+                )
+                .map(Message::LinkClicked),
+            }
+        };
 
         let toggle_view = button(center(if self.collapsed {
             icons::right_icon()
@@ -120,12 +237,12 @@ impl MdHelp {
             toggle_view.into()
         } else {
             let ftypes = row![
-                button("All")
-                    .on_press(Message::FuncTypeChange(None))
-                    .style(match self.state {
-                        None => button::success,
+                button("All").on_press(Message::FuncTypeChange(None)).style(
+                    match (&self.state, self.favorites) {
+                        (None, false) => button::success,
                         _ => button::primary,
-                    }),
+                    }
+                ),
                 button("Env")
                     .on_press(Message::FuncTypeChange(Some(FuncType::Env)))
                     .style(match self.state {
@@ -144,42 +261,178 @@ impl MdHelp {
                         Some(FuncType::Network) => button::success,
                         _ => button::primary,
                     }),
+                button("Favorites")
+                    .on_press(Message::ShowFavorites)
+                    .style(if self.favorites {
+                        button::success
+                    } else {
+                        button::primary
+                    }),
             ]
             .spacing(20)
             .padding(10);
-            let funcs: Vec<Element<_>> = list_functions(&self.functions, &self.state, &self.search)
-                .into_iter()
-                .enumerate()
-                .map(|(i, n)| {
-                    button(text(format!("{}  {}", n.0, n.1)))
-                        .on_press(Message::Function(n.0.clone(), n.1.to_string()))
+            let plugin_names: Vec<String> = self.plugins.iter().map(|p| p.name.clone()).collect();
+            let pfilter = row![
+                pick_list(
+                    plugin_names,
+                    self.plugin.clone(),
+                    |p| Message::PluginChange(Some(p))
+                )
+                .placeholder("All Plugins")
+                .width(Length::Fill),
+                icons::action(
+                    icons::cancel_icon(),
+                    "Show All Plugins",
+                    self.plugin.as_ref().map(|_| Message::PluginChange(None))
+                ),
+                icons::action(
+                    icons::refresh_icon(),
+                    "Reload Plugins",
+                    Some(Message::ReloadPlugins)
+                ),
+                toggler(self.group_plugins)
+                    .label("Group")
+                    .on_toggle(Message::GroupPlugins),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center)
+            .width(FUNC_WIDTH);
+            let mut funcs: Vec<(FuncType, &str)> =
+                list_functions(&self.functions, &self.state, &self.search)
+                    .into_iter()
+                    .filter(|(_, n)| {
+                        self.plugin
+                            .as_ref()
+                            .is_none_or(|p| plugins::plugin_of(n) == p)
+                    })
+                    .filter(|(t, n)| !self.favorites || self.bookmarks.is_favorite(t, n))
+                    .collect();
+            if self.group_plugins {
+                // stable sort keeps the type/name order within plugins
+                funcs.sort_by_key(|(_, n)| plugins::plugin_of(n));
+            }
+            let mut list = Column::new().width(FUNC_WIDTH);
+            let mut last_plugin = None;
+            for (i, (ty, name)) in funcs.into_iter().enumerate() {
+                let (plug, fname) = name.split_once('.').unwrap_or(("", name));
+                if self.group_plugins && last_plugin != Some(plug) {
+                    last_plugin = Some(plug);
+                    list = list.push(
+                        button(text(plug.to_string()).size(18))
+                            .on_press(Message::Plugin(plug.to_string()))
+                            .width(Length::Fill)
+                            .style(button::text),
+                    );
+                }
+                let star = self.star(ty.clone(), name.to_string());
+                let entry = button(row![
+                    text(format!("{ty}  {fname}")),
+                    horizontal_space(),
+                    text(plug.to_string()).size(12).style(text::secondary)
+                ])
+                .on_press(Message::Function(ty.clone(), name.to_string()))
+                .width(Length::Fill)
+                .style(if (i % 2) == 0 {
+                    secondary_even
+                } else {
+                    secondary_odd
+                });
+                if self.embedded {
+                    list = list.push(row![
+                        star,
+                        entry,
+                        icons::action(
+                            icons::pencil_icon(),
+                            "Insert",
+                            Some(Message::InsertTemplate(ty.clone(), name.to_string()))
+                        )
+                    ]);
+                } else {
+                    list = list.push(row![star, entry]);
+                }
+            }
+
+            let mut recent = Column::new().width(FUNC_WIDTH);
+            if !self.bookmarks.history.is_empty() {
+                recent = recent.push(text("Recent").size(18));
+            }
+            for (ty, name) in &self.bookmarks.history {
+                recent = recent.push(
+                    button(text(format!("{ty}  {name}")).size(14))
+                        .on_press(Message::Function(ty.clone(), name.clone()))
                         .width(Length::Fill)
-                        .style(if (i % 2) == 0 {
-                            secondary_even
-                        } else {
-                            secondary_odd
-                        })
-                        .into()
-                })
-                .collect();
-
-            let list = Column::from_vec(funcs).width(FUNC_WIDTH);
+                        .padding([2, 10])
+                        .style(button::text),
+                );
+            }
+
             let search = text_input("Search", &self.search)
                 .on_input(Message::SearchChange)
                 .padding(10)
                 .width(FUNC_WIDTH);
             row![
-                column![ftypes, search, scrollable(list)].spacing(10),
+                column![ftypes, pfilter, search, scrollable(list), recent].spacing(10),
                 toggle_view
             ]
             .into()
         };
 
-        let main = row![functions, scrollable(md)].spacing(10).padding(10);
-        column![controls, main].spacing(10).into()
+        let mut main = row![functions, scrollable(md).id(self.content_id.clone())]
+            .spacing(10)
+            .padding(10);
+        if self.show_outline && !self.outline.is_empty() {
+            let mut outline = Column::new().width(OUTLINE_WIDTH);
+            for h in &self.outline {
+                outline = outline.push(
+                    button(text(h.title.clone()).size(if h.level == 1 { 16 } else { 14 }))
+                        .on_press(Message::ScrollTo(h.position))
+                        .padding(
+                            iced::Padding::from([2, 5]).left(5.0 + 10.0 * (h.level - 1) as f32),
+                        )
+                        .width(Length::Fill)
+                        .style(button::text),
+                );
+            }
+            main = main.push(scrollable(outline));
+        }
+        let mut col = column![controls].spacing(10);
+        if let Some(query) = &self.find {
+            let status = if self.find_positions.is_empty() {
+                "No matches".to_string()
+            } else {
+                format!("{}/{}", self.find_current + 1, self.find_positions.len())
+            };
+            let has_matches = !self.find_positions.is_empty();
+            col = col.push(
+                row![
+                    text_input("Find in page", query)
+                        .id(self.find_id.clone())
+                        .on_input(Message::FindChange)
+                        .on_submit(Message::FindNext)
+                        .padding(5)
+                        .width(FUNC_WIDTH),
+                    text(status),
+                    icons::action(
+                        icons::arrow_up_icon(),
+                        "Previous",
+                        has_matches.then_some(Message::FindPrevious)
+                    ),
+                    icons::action(
+                        icons::arrow_down_icon(),
+                        "Next",
+                        has_matches.then_some(Message::FindNext)
+                    ),
+                    icons::action(icons::cancel_icon(), "Close", Some(Message::CloseFind)),
+                ]
+                .spacing(10)
+                .padding([0, 10])
+                .align_y(iced::Alignment::Center),
+            );
+        }
+        col.push(main).into()
     }
 
-    pub fn update(&mut self, message: Message) {
+    pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::LinkClicked(url) => {
                 match url.scheme() {
@@ -192,7 +445,23 @@ impl MdHelp {
                 }
             }
             Message::Home => {
-                self.markdown = markdown::parse(MAIN_HELP).collect();
+                self.set_document(MAIN_HELP.to_string());
+            }
+            Message::Index => {
+                self.page = None;
+                self.outline = vec![];
+                self.index = Some(index::FuncIndex::new(&self.functions));
+                self.refresh_find();
+            }
+            Message::IndexGroupChange(group) => {
+                if let Some(index) = &mut self.index {
+                    index.group = group;
+                }
+            }
+            Message::IndexTypeChange(ty) => {
+                if let Some(index) = &mut self.index {
+                    index.arg_type = ty;
+                }
             }
             Message::Book => {
                 _ = webbrowser::open("https://nadi-system.github.io/");
@@ -206,28 +475,191 @@ impl MdHelp {
             Message::SearchChange(s) => {
                 self.search = s;
             }
-            Message::Function(FuncType::Node, func) => {
-                if let Some(f) = self.functions.node(&func) {
-                    self.markdown = help!("node", func, f);
+            Message::Function(ty, func) => {
+                if let Some(help) = function_page(&self.functions, &ty, &func) {
+                    let page = FuncPage::new(help);
+                    self.outline = page.headings();
+                    self.page = Some(page);
+                    self.index = None;
+                    self.find_current = 0;
+                    self.refresh_find();
+                    self.bookmarks.visited(ty, func);
                 }
             }
-            Message::Function(FuncType::Network, func) => {
-                if let Some(f) = self.functions.network(&func) {
-                    self.markdown = help!("network", func, f);
-                }
+            Message::FuncTypeChange(state) => {
+                self.state = state;
+                self.favorites = false;
+            }
+            Message::ShowFavorites => {
+                self.state = None;
+                self.favorites = true;
+            }
+            Message::ToggleFavorite(ty, name) => {
+                self.bookmarks.toggle_favorite(ty, name);
             }
-            Message::Function(FuncType::Env, func) => {
-                if let Some(f) = self.functions.env(&func) {
-                    self.markdown = help!("env", func, f);
+            Message::PluginChange(plugin) => {
+                self.plugin = plugin;
+            }
+            Message::Plugin(name) => {
+                if let Some(p) = self.plugins.iter().find(|p| p.name == name) {
+                    self.set_document(p.to_markdown());
+                    self.plugin = Some(name);
                 }
             }
-            Message::FuncTypeChange(state) => {
-                self.state = state;
+            Message::GroupPlugins(g) => {
+                self.group_plugins = g;
+            }
+            Message::ReloadPlugins => {
+                self.reload_plugins();
             }
             Message::ThemeChange(t) => {
                 self.light_theme = t;
             }
+            Message::RunExample(ind) => {
+                if let Some(page) = &mut self.page {
+                    let sandbox = self.sandbox.get_or_insert_with(|| {
+                        examples::Sandbox::new(plugins::copy_functions(&self.functions))
+                    });
+                    page.run_example(ind, sandbox);
+                    // the output changes the positions of the headings
+                    self.outline = page.headings();
+                }
+            }
+            Message::ToggleOutline => {
+                self.show_outline = !self.show_outline;
+            }
+            Message::ScrollTo(y) => return self.scroll_to(y),
+            Message::OpenFind => {
+                if self.find.is_none() {
+                    self.find = Some(String::new());
+                }
+                return text_input::focus(self.find_id.clone());
+            }
+            Message::CloseFind => {
+                self.find = None;
+                self.refresh_find();
+            }
+            Message::FindChange(q) => {
+                self.find = Some(q);
+                self.find_current = 0;
+                self.refresh_find();
+                return self.scroll_to_match();
+            }
+            Message::FindNext | Message::FindPrevious => {
+                let n = self.find_positions.len();
+                if n > 0 {
+                    self.find_current = if matches!(message, Message::FindNext) {
+                        (self.find_current + 1) % n
+                    } else {
+                        (self.find_current + n - 1) % n
+                    };
+                    self.refresh_find();
+                    return self.scroll_to_match();
+                }
+            }
+            // handled in the main window
+            Message::InsertTemplate(..) => (),
+        }
+        Task::none()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        keyboard::on_key_press(|key, modifiers| match key.as_ref() {
+            Key::Character("f") if modifiers.command() => Some(Message::OpenFind),
+            Key::Named(Named::Escape) => Some(Message::CloseFind),
+            _ => None,
+        })
+    }
+
+    /// Show the markdown document instead of a function page
+    fn set_document(&mut self, md: String) {
+        self.page = None;
+        self.index = None;
+        self.outline = find::headings(&md);
+        self.source = md;
+        self.find_current = 0;
+        self.refresh_find();
+    }
+
+    /// Parse the current document again with the matches for the
+    /// find query highlighted
+    fn refresh_find(&mut self) {
+        let query = self.find.as_deref().unwrap_or_default();
+        self.find_positions = match &mut self.page {
+            _ if self.index.is_some() => vec![],
+            Some(page) => page.find(query, self.find_current),
+            None => {
+                let matches = find::find_matches(&self.source, query);
+                let md = find::highlight_matches(&self.source, &matches, Some(self.find_current));
+                self.markdown = markdown::parse(&md).collect();
+                let len = self.source.len().max(1) as f32;
+                matches.iter().map(|m| m.start as f32 / len).collect()
+            }
+        };
+    }
+
+    fn scroll_to_match(&self) -> Task<Message> {
+        match self.find_positions.get(self.find_current) {
+            Some(y) => self.scroll_to(*y),
+            None => Task::none(),
+        }
+    }
+
+    /// Scroll to the relative position in the page, the position is
+    /// estimated from the markdown source so it is approximate
+    fn scroll_to(&self, y: f32) -> Task<Message> {
+        scrollable::snap_to(
+            self.content_id.clone(),
+            scrollable::RelativeOffset { x: 0.0, y },
+        )
+    }
+
+    fn star(&self, ty: FuncType, name: String) -> Element<'_, Message> {
+        star_button(self.bookmarks.is_favorite(&ty, &name), ty, name)
+    }
+
+    /// Task template for the function, see [`call_template`]
+    pub fn template(&self, ty: &FuncType, name: &str) -> Option<(String, Option<Range<usize>>)> {
+        let (args, alias) = match ty {
+            FuncType::Node => (
+                self.functions.node(name)?.args(),
+                self.functions.node_alias(),
+            ),
+            FuncType::Network => (
+                self.functions.network(name)?.args(),
+                self.functions.network_alias(),
+            ),
+            FuncType::Env => (self.functions.env(name)?.args(), self.functions.env_alias()),
+        };
+        // use the short name unless another plugin has taken it
+        let name = match name.split_once('.') {
+            Some((_, short)) if alias.get(short).is_some_and(|f| f == name) => short,
+            _ => name,
+        };
+        Some(call_template(ty, name, &args))
+    }
+
+    /// Functions the help is shown for
+    pub fn functions(&self) -> &NadiFunctions {
+        &self.functions
+    }
+
+    /// Rebuild the functions list, loading the plugins again.
+    ///
+    /// The shared libraries already loaded are not unloaded, so this
+    /// picks up new plugins in the plugin directories but not the
+    /// changes in already loaded libraries.
+    pub fn reload_plugins(&mut self) {
+        self.functions = NadiFunctions::new();
+        self.plugins = plugins::plugins_info(&self.functions);
+        self.sandbox = None;
+        if self.index.is_some() {
+            self.index = Some(index::FuncIndex::new(&self.functions));
         }
+        self.plugin = self
+            .plugin
+            .take()
+            .filter(|p| self.plugins.iter().any(|pi| &pi.name == p));
     }
 
     pub fn theme(&self) -> Theme {
@@ -279,30 +711,92 @@ pub fn list_functions<'a>(
     func
 }
 
+/// Help for the function, None if the function doesn't exist
+pub fn function_page(functions: &NadiFunctions, ty: &FuncType, name: &str) -> Option<FuncHelp> {
+    match ty {
+        FuncType::Node => functions.node(name).map(|f| help!(FuncType::Node, name, f)),
+        FuncType::Network => functions
+            .network(name)
+            .map(|f| help!(FuncType::Network, name, f)),
+        FuncType::Env => functions.env(name).map(|f| help!(FuncType::Env, name, f)),
+    }
+}
+
+/// Markdown help for the function, None if the function doesn't exist
+pub fn function_help(functions: &NadiFunctions, ty: &FuncType, name: &str) -> Option<String> {
+    function_page(functions, ty, name).map(|h| h.to_markdown())
+}
+
+/// Short (one line) help for the function
+pub fn function_short_help(functions: &NadiFunctions, ty: &FuncType, name: &str) -> Option<String> {
+    match ty {
+        FuncType::Node => functions.node(name).map(|f| f.short_help().to_string()),
+        FuncType::Network => functions.network(name).map(|f| f.short_help().to_string()),
+        FuncType::Env => functions.env(name).map(|f| f.short_help().to_string()),
+    }
+}
+
+/// Task line calling the function with its arguments filled in.
+///
+/// Arguments with default values get their defaults, required ones
+/// get `...` as placeholder, and optional ones are left out. The
+/// range (in chars) of the placeholder for the first required
+/// argument is also returned so the cursor can be placed there.
+pub fn call_template(
+    ty: &FuncType,
+    name: &str,
+    args: &[FuncArg],
+) -> (String, Option<Range<usize>>) {
+    let mut templ = format!("{ty} {name}(");
+    let mut first = None;
+    let mut sep = "";
+    for arg in args {
+        let val = match &arg.category {
+            FuncArgType::Arg => "...".to_string(),
+            FuncArgType::DefArg(v) => v.to_string(),
+            FuncArgType::OptArg | FuncArgType::Args | FuncArgType::KwArgs => continue,
+        };
+        templ.push_str(&format!("{sep}{} = ", arg.name));
+        sep = ", ";
+        if first.is_none() && matches!(arg.category, FuncArgType::Arg) {
+            let start = templ.chars().count();
+            first = Some(start..(start + val.chars().count()));
+        }
+        templ.push_str(&val);
+    }
+    templ.push(')');
+    (templ, first)
+}
+
 pub fn help_to_markdown(
-    ty: &str,
+    ty: &FuncType,
     name: &str,
     args: &[FuncArg],
     short: &str,
     long: &str,
     code: &str,
 ) -> Vec<markdown::Item> {
-    let mut items = vec![];
-    let sig = args
-        .iter()
-        .map(|f| f.to_string())
-        .collect::<Vec<String>>()
-        .join(", ");
-    items.push(format!(
-        "# {ty} <span color=\"blue\">{name}</span>\n```python\n{ty} {name}({sig})\n```\n\n{short}"
-    ));
-    items.push("## Arguments".to_string());
-    args.iter()
-        .for_each(|f| items.push(format!("- `{}` => {}", f.to_string(), f.help)));
-    items.push("\n".to_string());
-    items.push(long[short.len()..].trim().to_string());
-    items.push(format!("# Code\n```rust\n{code}\n```\n"));
-    markdown::parse(&items.join("\n")).collect()
+    markdown::parse(&help_to_string(ty, name, args, short, long, code)).collect()
+}
+
+pub fn help_to_string(
+    ty: &FuncType,
+    name: &str,
+    args: &[FuncArg],
+    short: &str,
+    long: &str,
+    code: &str,
+) -> String {
+    FuncHelp::new(ty.clone(), name, args, short, long, code).to_markdown()
+}
+
+/// Toggle button to mark the function as favorite
+pub fn star_button<'a>(favorite: bool, ty: FuncType, name: String) -> Element<'a, Message> {
+    button(text(if favorite { "★" } else { "☆" }))
+        .on_press(Message::ToggleFavorite(ty, name))
+        .padding([5, 8])
+        .style(button::text)
+        .into()
 }
 
 pub fn md_style(light: bool) -> markdown::Style {
@@ -0,0 +1,271 @@
+//! Render the help markdown as ANSI formatted text for the terminal.
+use colored::{ColoredString, Colorize};
+use nadi_core::parser::tokenizer;
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+static INDENT: &str = "    ";
+
+#[derive(Default)]
+struct Writer {
+    out: String,
+    indent: usize,
+    line_start: bool,
+    strong: bool,
+    emphasis: bool,
+    heading: Option<HeadingLevel>,
+    link: Option<String>,
+    // numbering for the nested lists, None for bullet lists
+    lists: Vec<Option<u64>>,
+    code_block: Option<(String, String)>,
+    // rows of cells, first row is the header
+    table: Option<Vec<Vec<String>>>,
+}
+
+impl Writer {
+    fn newline(&mut self) {
+        self.out.push('\n');
+        self.line_start = true;
+    }
+
+    fn blankline(&mut self) {
+        if self.out.is_empty() {
+            return;
+        }
+        if !self.line_start {
+            self.newline();
+        }
+        if !self.out.ends_with("\n\n") {
+            self.newline();
+        }
+    }
+
+    fn push(&mut self, txt: &str) {
+        for (i, line) in txt.split('\n').enumerate() {
+            if i > 0 {
+                self.newline();
+            }
+            if line.is_empty() {
+                continue;
+            }
+            if self.line_start {
+                self.out.push_str(&INDENT.repeat(self.indent));
+                self.line_start = false;
+            }
+            self.out.push_str(&self.style(line).to_string());
+        }
+    }
+
+    fn style(&self, txt: &str) -> ColoredString {
+        let mut s = txt.normal();
+        if let Some(level) = self.heading {
+            s = s.bold();
+            if level == HeadingLevel::H1 {
+                s = s.underline();
+            }
+        }
+        if self.strong {
+            s = s.bold();
+        }
+        if self.emphasis {
+            s = s.italic();
+        }
+        if self.link.is_some() {
+            s = s.blue().underline();
+        }
+        s
+    }
+
+    fn code_block(&mut self, lang: &str, code: &str) {
+        let code = code.trim_end_matches('\n');
+        let lines: Vec<String> = match lang {
+            "task" | "tasks" | "net" | "network" | "sig" | "signature" => {
+                match tokenizer::get_tokens(code) {
+                    Ok(tokens) => tokens
+                        .iter()
+                        .map(|t| t.colored())
+                        .collect::<String>()
+                        .split('\n')
+                        .map(String::from)
+                        .collect(),
+                    Err(_) => code.split('\n').map(String::from).collect(),
+                }
+            }
+            _ => code
+                .split('\n')
+                .map(|l| l.bright_black().to_string())
+                .collect(),
+        };
+        self.blankline();
+        for line in lines {
+            self.out.push_str(&INDENT.repeat(self.indent + 1));
+            self.out.push_str(&line);
+            self.newline();
+        }
+        self.newline();
+    }
+
+    fn table(&mut self, rows: &[Vec<String>]) {
+        let ncols = rows.iter().map(|r| r.len()).max().unwrap_or_default();
+        let widths: Vec<usize> = (0..ncols)
+            .map(|i| {
+                rows.iter()
+                    .filter_map(|r| r.get(i))
+                    .map(|c| c.chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        self.blankline();
+        for (i, row) in rows.iter().enumerate() {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(j, w)| {
+                    let cell = format!("{:<w$}", row.get(j).map(String::as_str).unwrap_or(""));
+                    if i == 0 {
+                        cell.bold().to_string()
+                    } else {
+                        cell
+                    }
+                })
+                .collect();
+            self.out.push_str(&INDENT.repeat(self.indent));
+            self.out.push_str(cells.join("  ").trim_end());
+            self.newline();
+            if i == 0 {
+                let sep: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+                self.out.push_str(&INDENT.repeat(self.indent));
+                self.out.push_str(&sep.join("  "));
+                self.newline();
+            }
+        }
+    }
+}
+
+/// Convert the markdown text into ANSI formatted text, similar to how
+/// man pages are shown in the terminal
+pub fn markdown_to_ansi(md: &str) -> String {
+    let mut w = Writer {
+        line_start: true,
+        ..Default::default()
+    };
+    let parser = Parser::new_ext(md, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH);
+    for event in parser {
+        if let Some((_, code)) = &mut w.code_block {
+            match event {
+                Event::Text(t) => code.push_str(&t),
+                Event::End(TagEnd::CodeBlock) => {
+                    let (lang, code) = w.code_block.take().unwrap_or_default();
+                    w.code_block(&lang, &code);
+                }
+                _ => (),
+            }
+            continue;
+        }
+        if let Some(rows) = &mut w.table {
+            match event {
+                Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => rows.push(vec![]),
+                Event::Start(Tag::TableCell) => {
+                    if let Some(r) = rows.last_mut() {
+                        r.push(String::new());
+                    }
+                }
+                Event::Text(t) | Event::Code(t) => {
+                    if let Some(c) = rows.last_mut().and_then(|r| r.last_mut()) {
+                        c.push_str(&t);
+                    }
+                }
+                Event::End(TagEnd::Table) => {
+                    let rows = w.table.take().unwrap_or_default();
+                    w.table(&rows);
+                }
+                _ => (),
+            }
+            continue;
+        }
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Heading { level, .. } => {
+                    w.blankline();
+                    w.indent = if level == HeadingLevel::H1 { 0 } else { 1 };
+                    w.heading = Some(level);
+                }
+                Tag::Paragraph if w.lists.is_empty() => w.blankline(),
+                Tag::CodeBlock(kind) => {
+                    let lang = match kind {
+                        CodeBlockKind::Fenced(l) => l.to_string(),
+                        CodeBlockKind::Indented => String::new(),
+                    };
+                    w.code_block = Some((lang, String::new()));
+                }
+                Tag::List(start) => {
+                    if w.lists.is_empty() {
+                        w.blankline();
+                    } else if !w.line_start {
+                        w.newline();
+                    }
+                    w.lists.push(start);
+                }
+                Tag::Item => {
+                    if !w.line_start {
+                        w.newline();
+                    }
+                    let bullet = match w.lists.last_mut() {
+                        Some(Some(n)) => {
+                            *n += 1;
+                            format!("{}. ", *n - 1)
+                        }
+                        _ => "• ".to_string(),
+                    };
+                    w.out.push_str(&INDENT.repeat(w.indent + w.lists.len() - 1));
+                    w.out.push_str(&bullet);
+                    w.line_start = false;
+                }
+                Tag::Table(_) => w.table = Some(vec![]),
+                Tag::Emphasis => w.emphasis = true,
+                Tag::Strong => w.strong = true,
+                Tag::Link { dest_url, .. } => w.link = Some(dest_url.to_string()),
+                _ => (),
+            },
+            Event::End(tag) => match tag {
+                TagEnd::Heading(_) => {
+                    w.heading = None;
+                    w.newline();
+                    w.indent = 1;
+                }
+                TagEnd::Paragraph if w.lists.is_empty() => w.newline(),
+                TagEnd::List(_) => {
+                    w.lists.pop();
+                    if w.lists.is_empty() && !w.line_start {
+                        w.newline();
+                    }
+                }
+                TagEnd::Emphasis => w.emphasis = false,
+                TagEnd::Strong => w.strong = false,
+                TagEnd::Link => {
+                    if let Some(url) = w.link.take() {
+                        w.push(&format!(" <{url}>"));
+                    }
+                }
+                _ => (),
+            },
+            Event::Text(t) => w.push(&t),
+            Event::Code(c) => {
+                if w.line_start {
+                    w.out.push_str(&INDENT.repeat(w.indent));
+                    w.line_start = false;
+                }
+                w.out.push_str(&c.yellow().to_string());
+            }
+            Event::SoftBreak => w.push(" "),
+            Event::HardBreak => w.newline(),
+            Event::Rule => {
+                w.blankline();
+                w.push(&"-".repeat(40));
+                w.newline();
+            }
+            _ => (),
+        }
+    }
+    w.out.trim_end().to_string()
+}
@@ -0,0 +1,128 @@
+//! Run the example tasks from the function help.
+use nadi_core::attrs::AttrMap;
+use nadi_core::functions::NadiFunctions;
+use nadi_core::network::Network;
+use nadi_core::parser::{network, tasks, tokenizer};
+use nadi_core::tasks::{TaskContext, TaskType};
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd};
+use std::io::Read;
+use std::ops::Range;
+
+/// Sample network the examples are run on
+pub static SAMPLE_NETWORK: &str = include_str!("../../examples/scioto.network");
+
+/// Code blocks with tasks from the help text, along with the byte
+/// range of the whole block in the text
+pub fn extract_examples(help: &str) -> Vec<(Range<usize>, String)> {
+    let mut examples = vec![];
+    let mut code: Option<(Range<usize>, String)> = None;
+    for (event, range) in Parser::new(help).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang)))
+                if matches!(lang.as_ref(), "task" | "tasks") =>
+            {
+                code = Some((range, String::new()));
+            }
+            Event::Text(t) => {
+                if let Some((_, c)) = &mut code {
+                    c.push_str(&t);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((r, c)) = code.take() {
+                    examples.push((r, c.trim_end().to_string()));
+                }
+            }
+            _ => (),
+        }
+    }
+    examples
+}
+
+/// Network from the [`SAMPLE_NETWORK`], built the same way as
+/// [`Network::from_file`] does
+fn sample_network() -> Result<Network, String> {
+    let tokens = tokenizer::get_tokens(SAMPLE_NETWORK).map_err(|e| e.to_string())?;
+    let paths = network::parse(tokens).map_err(|e| e.to_string())?;
+    let mut net = Network::default();
+    for path in paths {
+        for name in [&path.start, &path.end] {
+            if net.node_by_name(name).is_none() {
+                net.insert_node_by_name(name);
+            }
+        }
+        let (Some(inp), Some(out)) = (net.node_by_name(&path.start), net.node_by_name(&path.end))
+        else {
+            continue;
+        };
+        inp.lock().set_output(out.clone());
+        out.lock().add_input(inp.clone());
+    }
+    net.reorder();
+    net.set_levels();
+    Ok(net)
+}
+
+/// Scratch [`TaskContext`] to run the examples in, so they don't
+/// modify the network the user is working on
+pub struct Sandbox {
+    ctx: TaskContext,
+}
+
+impl Sandbox {
+    pub fn new(functions: NadiFunctions) -> Self {
+        Self {
+            ctx: TaskContext {
+                network: Network::default(),
+                functions,
+                env: AttrMap::new(),
+            },
+        }
+    }
+
+    fn reset(&mut self) -> Result<(), String> {
+        self.ctx.network = sample_network()?;
+        self.ctx.env = AttrMap::new();
+        Ok(())
+    }
+
+    /// Run the example on a fresh copy of the sample network,
+    /// returning the output or the first error, None if the stdout
+    /// is already being captured somewhere else (e.g. the terminal)
+    pub fn run(&mut self, example: &str) -> Option<Result<String, String>> {
+        let mut buf = gag::BufferRedirect::stdout().ok()?;
+        let res = self.run_captured(example, &mut buf);
+        drop(buf);
+        Some(res)
+    }
+
+    fn run_captured(
+        &mut self,
+        example: &str,
+        buf: &mut gag::BufferRedirect,
+    ) -> Result<String, String> {
+        let tokens = tokenizer::get_tokens(example).map_err(|e| e.to_string())?;
+        let tasks = tasks::parse(tokens).map_err(|e| e.to_string())?;
+        self.reset()?;
+        let mut output = String::new();
+        for task in tasks {
+            // these are for interactive use
+            if matches!(task.ty, TaskType::Exit | TaskType::Help(..)) {
+                continue;
+            }
+            let res =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| self.ctx.execute(task)));
+            buf.read_to_string(&mut output).map_err(|e| e.to_string())?;
+            match res {
+                Ok(Ok(Some(p))) => {
+                    output.push_str(&p);
+                    output.push('\n');
+                }
+                Ok(Ok(None)) => (),
+                Ok(Err(e)) => return Err(format!("{output}{e}")),
+                Err(_) => return Err(format!("{output}Task panicked")),
+            }
+        }
+        Ok(output)
+    }
+}
@@ -0,0 +1,91 @@
+//! Favorite and recently viewed functions, saved across sessions.
+use super::FuncType;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Number of recently viewed functions to remember
+static HISTORY_LEN: usize = 10;
+
+#[derive(Default)]
+pub struct Bookmarks {
+    pub favorites: Vec<(FuncType, String)>,
+    /// Recently viewed functions, latest first
+    pub history: VecDeque<(FuncType, String)>,
+}
+
+impl Bookmarks {
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("nadi").join("help-bookmarks.txt"))
+    }
+
+    /// Load the bookmarks saved in the config directory, each line of
+    /// the file is `favorite|recent <type> <function>`
+    pub fn load() -> Self {
+        let mut bm = Self::default();
+        let Some(txt) = Self::path().and_then(|p| std::fs::read_to_string(p).ok()) else {
+            return bm;
+        };
+        for line in txt.lines() {
+            let mut parts = line.split_whitespace();
+            let (Some(kind), Some(ty), Some(name)) = (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            let Ok(ty) = FuncType::from_str(ty) else {
+                continue;
+            };
+            match kind {
+                "favorite" => bm.favorites.push((ty, name.to_string())),
+                "recent" if bm.history.len() < HISTORY_LEN => {
+                    bm.history.push_back((ty, name.to_string()))
+                }
+                _ => (),
+            }
+        }
+        bm
+    }
+
+    /// Save the bookmarks, errors are ignored as they are not
+    /// important enough to bother the user with
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        let mut txt = String::new();
+        for (ty, name) in &self.favorites {
+            txt.push_str(&format!("favorite {ty} {name}\n"));
+        }
+        for (ty, name) in &self.history {
+            txt.push_str(&format!("recent {ty} {name}\n"));
+        }
+        if let Some(dir) = path.parent() {
+            _ = std::fs::create_dir_all(dir);
+        }
+        _ = std::fs::write(path, txt);
+    }
+
+    pub fn is_favorite(&self, ty: &FuncType, name: &str) -> bool {
+        self.favorites.iter().any(|(t, n)| t == ty && n == name)
+    }
+
+    pub fn toggle_favorite(&mut self, ty: FuncType, name: String) {
+        if let Some(i) = self
+            .favorites
+            .iter()
+            .position(|(t, n)| t == &ty && n == &name)
+        {
+            self.favorites.remove(i);
+        } else {
+            self.favorites.push((ty, name));
+        }
+        self.save();
+    }
+
+    pub fn visited(&mut self, ty: FuncType, name: String) {
+        self.history.retain(|(t, n)| !(t == &ty && n == &name));
+        self.history.push_front((ty, name));
+        self.history.truncate(HISTORY_LEN);
+        self.save();
+    }
+}
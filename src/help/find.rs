@@ -0,0 +1,102 @@
+//! Outline and in-page find for the help documents.
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+use std::ops::Range;
+
+pub struct Heading {
+    pub level: usize,
+    pub title: String,
+    /// Position of the heading in the document (0 to 1), to scroll to it
+    pub position: f32,
+}
+
+/// Headings from the markdown document
+pub fn headings(md: &str) -> Vec<Heading> {
+    let mut headings = vec![];
+    let mut current: Option<(usize, usize, String)> = None;
+    for (event, range) in Parser::new(md).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((heading_level(level), range.start, String::new()))
+            }
+            Event::Text(t) | Event::Code(t) => {
+                if let Some((_, _, title)) = &mut current {
+                    title.push_str(&t);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, start, title)) = current.take() {
+                    headings.push(Heading {
+                        level,
+                        title,
+                        position: start as f32 / md.len().max(1) as f32,
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+    headings
+}
+
+fn heading_level(level: HeadingLevel) -> usize {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// Byte ranges of the (case insensitive) matches for the query in
+/// the text of the markdown, code blocks are not searched as they
+/// can't be highlighted
+pub fn find_matches(md: &str, query: &str) -> Vec<Range<usize>> {
+    // backticks would break the inline code used for highlighting
+    if query.is_empty() || query.contains('`') {
+        return vec![];
+    }
+    let query = query.to_lowercase();
+    let mut matches = vec![];
+    let mut in_code = false;
+    for (event, range) in Parser::new(md).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code = true,
+            Event::End(TagEnd::CodeBlock) => in_code = false,
+            // search the source so the ranges are correct even
+            // with escaped characters in the text
+            Event::Text(_) if !in_code => {
+                let src = md[range.clone()].to_lowercase();
+                // lowercase can change the length of some characters
+                if src.len() != range.len() {
+                    continue;
+                }
+                matches.extend(
+                    src.match_indices(&query)
+                        .map(|(i, m)| (range.start + i)..(range.start + i + m.len())),
+                );
+            }
+            _ => (),
+        }
+    }
+    matches
+}
+
+/// Markdown with the matches highlighted as inline code, the current
+/// match is also made bold
+pub fn highlight_matches(md: &str, matches: &[Range<usize>], current: Option<usize>) -> String {
+    let mut out = String::with_capacity(md.len() + matches.len() * 2);
+    let mut last = 0;
+    for (i, m) in matches.iter().enumerate() {
+        out.push_str(&md[last..m.start]);
+        if Some(i) == current {
+            out.push_str(&format!("**`{}`**", &md[m.clone()]));
+        } else {
+            out.push_str(&format!("`{}`", &md[m.clone()]));
+        }
+        last = m.end;
+    }
+    out.push_str(&md[last..]);
+    out
+}
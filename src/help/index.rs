@@ -0,0 +1,183 @@
+//! Index of the functions by their argument types and categories.
+use super::{FuncType, Message, list_functions, secondary_even, secondary_odd};
+use abi_stable::std_types::RVec;
+use iced::widget::{Column, button, column, horizontal_space, pick_list, row, text};
+use iced::{Element, Font, Length, font};
+use nadi_core::functions::{FuncArg, FuncArgType, NadiFunctions};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IndexGroup {
+    ArgType,
+    Category,
+}
+
+struct IndexEntry {
+    ty: FuncType,
+    name: String,
+    args: Vec<FuncArg>,
+}
+
+pub struct FuncIndex {
+    entries: Vec<IndexEntry>,
+    pub group: IndexGroup,
+    /// only show the functions with an argument of this type
+    pub arg_type: Option<String>,
+}
+
+/// Type of the argument without the references and `Option`, so that
+/// `& Template` and `Option < Template >` are both `Template`
+pub fn base_type(ty: &str) -> String {
+    let mut ty: &str = &ty.replace(' ', "");
+    let ty = loop {
+        if let Some(t) = ty.strip_prefix('&') {
+            ty = t.strip_prefix("mut").unwrap_or(t);
+        } else if let Some(t) = ty.strip_prefix("Option<").and_then(|t| t.strip_suffix('>')) {
+            ty = t;
+        } else {
+            break ty;
+        }
+    };
+    match ty {
+        "str" => "String".to_string(),
+        t => t.to_string(),
+    }
+}
+
+fn category(arg: &FuncArg) -> &'static str {
+    match arg.category {
+        FuncArgType::Arg => "Required",
+        FuncArgType::OptArg => "Optional",
+        FuncArgType::DefArg(_) => "Default Value",
+        FuncArgType::Args => "Variable Arguments",
+        FuncArgType::KwArgs => "Variable Keyword Arguments",
+    }
+}
+
+impl FuncIndex {
+    pub fn new(functions: &NadiFunctions) -> Self {
+        let entries = list_functions(functions, &None, "")
+            .into_iter()
+            .map(|(ty, name)| {
+                let args: RVec<FuncArg> = match ty {
+                    FuncType::Node => functions.node(name).map(|f| f.args()),
+                    FuncType::Network => functions.network(name).map(|f| f.args()),
+                    FuncType::Env => functions.env(name).map(|f| f.args()),
+                }
+                .unwrap_or_default();
+                IndexEntry {
+                    ty,
+                    name: name.to_string(),
+                    args: args.into_iter().collect(),
+                }
+            })
+            .collect();
+        Self {
+            entries,
+            group: IndexGroup::ArgType,
+            arg_type: None,
+        }
+    }
+
+    /// All the argument types used in the functions
+    pub fn arg_types(&self) -> Vec<String> {
+        let mut types: Vec<String> = self
+            .entries
+            .iter()
+            .flat_map(|e| e.args.iter().map(|a| base_type(&a.ty)))
+            .collect();
+        types.sort();
+        types.dedup();
+        types
+    }
+
+    /// Functions grouped by the argument type or category, with the
+    /// names of the arguments that put them in the group
+    fn groups(&self, state: &Option<FuncType>) -> BTreeMap<String, Vec<(&IndexEntry, Vec<&str>)>> {
+        let mut groups: BTreeMap<String, Vec<(&IndexEntry, Vec<&str>)>> = BTreeMap::new();
+        let entries = self
+            .entries
+            .iter()
+            .filter(|e| state.as_ref().is_none_or(|s| s == &e.ty))
+            .filter(|e| {
+                self.arg_type
+                    .as_ref()
+                    .is_none_or(|t| e.args.iter().any(|a| &base_type(&a.ty) == t))
+            });
+        for entry in entries {
+            let mut keys: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+            for arg in &entry.args {
+                let key = match self.group {
+                    IndexGroup::ArgType => base_type(&arg.ty),
+                    IndexGroup::Category => category(arg).to_string(),
+                };
+                keys.entry(key).or_default().push(arg.name.as_str());
+            }
+            if entry.args.is_empty() {
+                keys.insert("No Arguments".to_string(), vec![]);
+            }
+            for (key, args) in keys {
+                groups.entry(key).or_default().push((entry, args));
+            }
+        }
+        groups
+    }
+
+    pub fn view(&self, state: &Option<FuncType>) -> Element<'_, Message> {
+        let bold = Font {
+            weight: font::Weight::Bold,
+            ..Font::default()
+        };
+        let group_button = |label, group| {
+            button(label)
+                .on_press(Message::IndexGroupChange(group))
+                .style(if self.group == group {
+                    button::success
+                } else {
+                    button::primary
+                })
+        };
+        let controls = row![
+            text("Group by:"),
+            group_button("Argument Type", IndexGroup::ArgType),
+            group_button("Argument Category", IndexGroup::Category),
+            horizontal_space(),
+            text("Has argument of type:"),
+            pick_list(self.arg_types(), self.arg_type.clone(), |t| {
+                Message::IndexTypeChange(Some(t))
+            })
+            .placeholder("Any"),
+            button("Any").on_press_maybe(
+                self.arg_type
+                    .as_ref()
+                    .map(|_| Message::IndexTypeChange(None))
+            ),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let mut list = Column::new().spacing(2);
+        for (key, funcs) in self.groups(state) {
+            list = list.push(text(format!("{key} ({})", funcs.len())).size(20).font(bold));
+            for (i, (entry, args)) in funcs.into_iter().enumerate() {
+                list = list.push(
+                    button(row![
+                        text(format!("{}  {}", entry.ty, entry.name)),
+                        horizontal_space(),
+                        text(args.join(", ")).size(12).style(text::secondary)
+                    ])
+                    .on_press(Message::Function(entry.ty.clone(), entry.name.clone()))
+                    .width(Length::Fill)
+                    .style(if (i % 2) == 0 {
+                        secondary_even
+                    } else {
+                        secondary_odd
+                    }),
+                );
+            }
+        }
+        column![text("Function Index").size(32).font(bold), controls, list]
+            .spacing(10)
+            .into()
+    }
+}
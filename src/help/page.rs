@@ -0,0 +1,452 @@
+//! Structured help page for a single function.
+use super::examples::{Sandbox, extract_examples};
+use super::{FuncType, Message, find, md_style, star_button};
+use crate::editor::my_hl::{Highlight, NadiFileType, NadiHighlighter};
+use iced::widget::{
+    Column, button, column, container, markdown, rich_text, row, span, text, text::Span,
+};
+use iced::{Element, Font, Length, Theme, font};
+use iced_core::text::highlighter::Highlighter;
+use nadi_core::functions::{FuncArg, FuncArgType};
+
+static ARG_COLUMNS: [(&str, u16); 5] = [
+    ("Name", 2),
+    ("Type", 2),
+    ("Default", 2),
+    ("Required", 1),
+    ("Description", 5),
+];
+
+#[derive(Clone, Debug)]
+pub struct ArgHelp {
+    pub name: String,
+    pub ty: String,
+    pub default: Option<String>,
+    pub required: bool,
+    /// `*` for variable arguments and `**` for variable keyword arguments
+    pub variadic: &'static str,
+    pub help: String,
+}
+
+impl From<&FuncArg> for ArgHelp {
+    fn from(arg: &FuncArg) -> Self {
+        let (default, required, variadic) = match &arg.category {
+            FuncArgType::Arg => (None, true, ""),
+            FuncArgType::OptArg => (None, false, ""),
+            FuncArgType::DefArg(v) => (Some(v.to_string()), false, ""),
+            FuncArgType::Args => (None, false, "*"),
+            FuncArgType::KwArgs => (None, false, "**"),
+        };
+        Self {
+            name: arg.name.to_string(),
+            ty: arg.ty.to_string(),
+            default,
+            required,
+            variadic,
+            help: arg.help.to_string(),
+        }
+    }
+}
+
+impl ArgHelp {
+    fn cells(&self) -> [String; 5] {
+        [
+            format!("{}{}", self.variadic, self.name),
+            self.ty.clone(),
+            self.default.clone().unwrap_or_else(|| "-".into()),
+            if self.required { "yes" } else { "no" }.into(),
+            self.help.clone(),
+        ]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FuncHelp {
+    pub ty: FuncType,
+    pub name: String,
+    pub args: Vec<ArgHelp>,
+    pub short: String,
+    /// Help text without the short help
+    pub long: String,
+    pub code: String,
+}
+
+impl FuncHelp {
+    pub fn new(
+        ty: FuncType,
+        name: &str,
+        args: &[FuncArg],
+        short: &str,
+        help: &str,
+        code: &str,
+    ) -> Self {
+        let short = short.trim();
+        let help = help.trim();
+        // short help is usually the first line of the help, but the
+        // plugins can define it to be anything
+        let long = help.strip_prefix(short).unwrap_or(help).trim();
+        Self {
+            ty,
+            name: name.to_string(),
+            args: args.iter().map(ArgHelp::from).collect(),
+            short: short.to_string(),
+            long: long.to_string(),
+            code: code.to_string(),
+        }
+    }
+
+    /// Signature of the function split into highlighted pieces
+    pub fn signature(&self) -> Vec<(String, Highlight)> {
+        let mut sig = vec![
+            (self.ty.to_string(), Highlight::Keyword),
+            (" ".into(), Highlight::None),
+            (self.name.clone(), Highlight::Function),
+            ("(".into(), Highlight::Paren),
+        ];
+        let multiline = self.args.len() > 2;
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                sig.push((",".into(), Highlight::Symbol));
+                if !multiline {
+                    sig.push((" ".into(), Highlight::None));
+                }
+            }
+            if multiline {
+                sig.push(("\n    ".into(), Highlight::None));
+            }
+            if !arg.variadic.is_empty() {
+                sig.push((arg.variadic.into(), Highlight::Symbol));
+                sig.push((arg.name.clone(), Highlight::Variable));
+                continue;
+            }
+            sig.push((arg.name.clone(), Highlight::Variable));
+            sig.push((": ".into(), Highlight::Symbol));
+            sig.push((format!("'{}'", arg.ty), Highlight::String));
+            if let Some(def) = &arg.default {
+                sig.push((" = ".into(), Highlight::Symbol));
+                let mut hl = NadiHighlighter::new(&NadiFileType::Tasks.into());
+                sig.extend(
+                    hl.highlight_line(def)
+                        .map(|(r, h)| (def[r].to_string(), h.hl))
+                        .collect::<Vec<_>>(),
+                );
+            }
+        }
+        if multiline {
+            sig.push(("\n".into(), Highlight::None));
+        }
+        sig.push((")".into(), Highlight::Paren));
+        sig
+    }
+
+    pub fn signature_text(&self) -> String {
+        self.signature().into_iter().map(|s| s.0).collect()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let sig = self.signature_text();
+        let mut md = format!(
+            "# {} {}\n```signature\n{sig}\n```\n\n{}\n\n## Arguments\n",
+            self.ty, self.name, self.short
+        );
+        if self.args.is_empty() {
+            md.push_str("No Arguments\n");
+        } else {
+            let header: Vec<&str> = ARG_COLUMNS.iter().map(|c| c.0).collect();
+            md.push_str(&format!("| {} |\n", header.join(" | ")));
+            md.push_str(&format!("|{}\n", "---|".repeat(header.len())));
+            for arg in &self.args {
+                let cells: Vec<String> = arg
+                    .cells()
+                    .iter()
+                    .map(|c| c.replace('|', "\\|").replace('\n', " "))
+                    .collect();
+                md.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+        }
+        md.push_str(&format!("\n{}\n", self.long));
+        md.push_str(&format!("# Code\n```rust\n{}\n```\n", self.code));
+        md
+    }
+}
+
+/// Markdown text along with its parsed items
+struct MdPart {
+    source: String,
+    items: Vec<markdown::Item>,
+}
+
+impl MdPart {
+    fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            items: markdown::parse(source).collect(),
+        }
+    }
+}
+
+/// Part of the long help, examples are shown separately so they can be run
+enum LongPart {
+    Markdown(MdPart),
+    Example(usize),
+}
+
+pub struct Example {
+    pub code: String,
+    /// Output (or error) from the last run
+    pub output: Option<Result<String, String>>,
+    /// The last run was refused as the stdout was already captured
+    pub busy: bool,
+}
+
+/// Function help along with the markdown parsed for the GUI
+pub struct FuncPage {
+    pub help: FuncHelp,
+    pub examples: Vec<Example>,
+    short: MdPart,
+    long: Vec<LongPart>,
+    code: Vec<markdown::Item>,
+}
+
+impl FuncPage {
+    pub fn new(help: FuncHelp) -> Self {
+        let mut long = vec![];
+        let mut examples = vec![];
+        let mut last = 0;
+        for (range, code) in extract_examples(&help.long) {
+            long.push(LongPart::Markdown(MdPart::new(
+                &help.long[last..range.start],
+            )));
+            long.push(LongPart::Example(examples.len()));
+            examples.push(Example {
+                code,
+                output: None,
+                busy: false,
+            });
+            last = range.end;
+        }
+        long.push(LongPart::Markdown(MdPart::new(&help.long[last..])));
+        Self {
+            short: MdPart::new(&help.short),
+            long,
+            examples,
+            code: markdown::parse(&format!("```rust\n{}\n```", help.code)).collect(),
+            help,
+        }
+    }
+
+    pub fn run_example(&mut self, ind: usize, sandbox: &mut Sandbox) {
+        if let Some(ex) = self.examples.get_mut(ind) {
+            match sandbox.run(&ex.code) {
+                Some(out) => {
+                    ex.output = Some(out);
+                    ex.busy = false;
+                }
+                None => ex.busy = true,
+            }
+        }
+    }
+
+    /// Headings of the page as it is shown, the positions are
+    /// estimated from the lines of each part drawn on the page
+    pub fn headings(&self) -> Vec<find::Heading> {
+        let lines = |s: &str| s.lines().count().max(1) as f32;
+        let heading = |level, title: &str| find::Heading {
+            level,
+            title: title.to_string(),
+            position: 0.0,
+        };
+        // height of the parts in lines, and the headings in them with
+        // their positions relative to the part
+        let mut parts: Vec<(f32, Vec<find::Heading>)> = vec![
+            (
+                2.0,
+                vec![heading(1, &format!("{} {}", self.help.ty, self.help.name))],
+            ),
+            (lines(&self.help.signature_text()) + 1.0, vec![]),
+            (lines(&self.help.short), vec![]),
+            (1.5, vec![heading(2, "Arguments")]),
+            ((self.help.args.len() + 1) as f32, vec![]),
+        ];
+        for part in &self.long {
+            parts.push(match part {
+                LongPart::Markdown(md) => (lines(&md.source), find::headings(&md.source)),
+                LongPart::Example(i) => {
+                    let ex = &self.examples[*i];
+                    let output = match &ex.output {
+                        Some(Ok(o) | Err(o)) => lines(o) + 1.0,
+                        None => 0.0,
+                    } + if ex.busy { 1.0 } else { 0.0 };
+                    (lines(&ex.code) + 2.0 + output, vec![])
+                }
+            });
+        }
+        parts.push((1.5, vec![heading(1, "Code")]));
+        parts.push((lines(&self.help.code) + 1.0, vec![]));
+
+        let total = parts.iter().map(|p| p.0).sum::<f32>().max(1.0);
+        let mut offset = 0.0;
+        let mut headings = vec![];
+        for (height, hs) in parts {
+            headings.extend(hs.into_iter().map(|h| find::Heading {
+                position: (offset + h.position * height) / total,
+                ..h
+            }));
+            offset += height;
+        }
+        headings
+    }
+
+    /// Highlight the matches for the query in the help text,
+    /// returning the approximate position (0 to 1) of each match
+    pub fn find(&mut self, query: &str, current: usize) -> Vec<f32> {
+        let mut parts: Vec<&mut MdPart> = vec![&mut self.short];
+        parts.extend(self.long.iter_mut().filter_map(|p| match p {
+            LongPart::Markdown(md) => Some(md),
+            LongPart::Example(_) => None,
+        }));
+        let total = parts.iter().map(|p| p.source.len()).sum::<usize>().max(1) as f32;
+        let mut positions = vec![];
+        let mut offset = 0;
+        for part in parts {
+            let matches = find::find_matches(&part.source, query);
+            let cur = current
+                .checked_sub(positions.len())
+                .filter(|c| *c < matches.len());
+            let md = find::highlight_matches(&part.source, &matches, cur);
+            part.items = markdown::parse(&md).collect();
+            positions.extend(matches.iter().map(|m| (offset + m.start) as f32 / total));
+            offset += part.source.len();
+        }
+        positions
+    }
+
+    pub fn view(&self, light: bool, favorite: bool) -> Element<'_, Message> {
+        let theme = if light { Theme::Light } else { Theme::Dark };
+        let bold = Font {
+            weight: font::Weight::Bold,
+            ..Font::default()
+        };
+        let sig: Vec<_> = self
+            .help
+            .signature()
+            .into_iter()
+            .map(|(s, h)| span(s).color_maybe(h.to_format(&theme).color))
+            .collect();
+        let signature = container(rich_text(sig).font(Font::MONOSPACE))
+            .padding(10)
+            .width(Length::Fill)
+            .style(container::rounded_box);
+
+        let cell_row = |cells: [String; 5], font: Font| {
+            row(ARG_COLUMNS
+                .iter()
+                .zip(cells)
+                .map(|((_, w), c)| text(c).font(font).width(Length::FillPortion(*w)).into()))
+            .spacing(10)
+            .padding(5)
+        };
+        let mut args = Column::new().push(
+            container(cell_row(ARG_COLUMNS.map(|c| c.0.to_string()), bold))
+                .style(container::rounded_box),
+        );
+        for (i, arg) in self.help.args.iter().enumerate() {
+            let r = container(cell_row(arg.cells(), Font::default())).width(Length::Fill);
+            args = args.push(if i % 2 == 1 {
+                r.style(container::rounded_box)
+            } else {
+                r
+            });
+        }
+        if self.help.args.is_empty() {
+            args = args.push(text("No Arguments"));
+        }
+
+        let mut long = Column::new().spacing(10);
+        for part in &self.long {
+            long = long.push(match part {
+                LongPart::Markdown(md) => {
+                    markdown::view(&md.items, markdown::Settings::default(), md_style(light))
+                        .map(Message::LinkClicked)
+                }
+                LongPart::Example(i) => self.example_view(*i, &theme),
+            });
+        }
+
+        column![
+            row![
+                text(format!("{} {}", self.help.ty, self.help.name))
+                    .size(32)
+                    .font(bold),
+                star_button(favorite, self.help.ty.clone(), self.help.name.clone())
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            signature,
+            markdown::view(
+                &self.short.items,
+                markdown::Settings::default(),
+                md_style(light)
+            )
+            .map(Message::LinkClicked),
+            text("Arguments").size(24).font(bold),
+            args,
+            long,
+            text("Code").size(24).font(bold),
+            markdown::view(&self.code, markdown::Settings::default(), md_style(light))
+                .map(Message::LinkClicked),
+        ]
+        .spacing(10)
+        .into()
+    }
+
+    fn example_view(&self, ind: usize, theme: &Theme) -> Element<'_, Message> {
+        let ex = &self.examples[ind];
+        let code = container(rich_text(highlight_tasks(&ex.code, theme)).font(Font::MONOSPACE))
+            .padding(10)
+            .width(Length::Fill)
+            .style(container::rounded_box);
+        let mut col = column![
+            code,
+            button("Run example")
+                .on_press(Message::RunExample(ind))
+                .style(button::secondary)
+        ]
+        .spacing(5);
+        if ex.busy {
+            col = col.push(
+                text("Busy: the output is being captured elsewhere, try again later")
+                    .style(text::secondary),
+            );
+        }
+        if let Some(out) = &ex.output {
+            let out = match out {
+                Ok(o) if o.trim().is_empty() => text("Ran successfully (no output)"),
+                Ok(o) => text(o.trim_end().to_string()),
+                Err(e) => text(e.trim_end().to_string()).style(text::danger),
+            };
+            col = col.push(
+                container(out.font(Font::MONOSPACE))
+                    .padding(10)
+                    .width(Length::Fill)
+                    .style(container::bordered_box),
+            );
+        }
+        col.into()
+    }
+}
+
+/// Tasks code highlighted the same way as the editor
+fn highlight_tasks<'a>(code: &str, theme: &Theme) -> Vec<Span<'a, Message>> {
+    let mut hl = NadiHighlighter::new(&NadiFileType::Tasks.into());
+    let mut spans = vec![];
+    for (i, line) in code.lines().enumerate() {
+        if i > 0 {
+            spans.push(span("\n"));
+        }
+        for (r, h) in hl.highlight_line(line).collect::<Vec<_>>() {
+            spans.push(span(line[r].to_string()).color_maybe(h.to_format(theme).color));
+        }
+    }
+    spans
+}
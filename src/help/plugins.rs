@@ -0,0 +1,165 @@
+//! Information about the plugins the functions are loaded from.
+use abi_stable::library::{RootModule, lib_header_from_path};
+use abi_stable::std_types::{RHashMap, RString, Tuple2};
+use nadi_core::functions::NadiFunctions;
+use nadi_core::plugins::{NadiExternalPlugin_Ref, NadiPlugin, load_library};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+
+/// Name and ABI version of the plugin library, None if it is not one
+type LibInfo = Option<(String, String)>;
+
+/// Plugin libraries already looked at, they are never unloaded, so
+/// each one is only loaded once here
+static EXTERNAL_PLUGINS: LazyLock<Mutex<HashMap<PathBuf, LibInfo>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Clone, Debug)]
+pub struct PluginInfo {
+    pub name: String,
+    /// version of nadi_core the plugin was compiled against
+    pub abi_version: String,
+    /// Path to the shared library, None for the plugins internal to nadi_core
+    pub path: Option<PathBuf>,
+    pub env: Vec<String>,
+    pub node: Vec<String>,
+    pub network: Vec<String>,
+}
+
+impl PluginInfo {
+    pub fn is_internal(&self) -> bool {
+        self.path.is_none()
+    }
+
+    pub fn count(&self) -> usize {
+        self.env.len() + self.node.len() + self.network.len()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!(
+            "# Plugin {}\n\n- ABI: `{}`\n- Source: {}\n- Functions: {}\n",
+            self.name,
+            self.abi_version,
+            match &self.path {
+                Some(p) => format!("`{}`", p.to_string_lossy()),
+                None => "Internal (nadi_core)".to_string(),
+            },
+            self.count()
+        );
+        for (ty, funcs) in [
+            ("Env", &self.env),
+            ("Node", &self.node),
+            ("Network", &self.network),
+        ] {
+            if funcs.is_empty() {
+                continue;
+            }
+            md.push_str(&format!("\n## {ty} Functions\n"));
+            for f in funcs {
+                md.push_str(&format!("- `{f}`\n"));
+            }
+        }
+        md
+    }
+}
+
+/// Name of the plugin from the full function name (`plugin.function`)
+pub fn plugin_of(func: &str) -> &str {
+    func.split_once('.').map(|(p, _)| p).unwrap_or_default()
+}
+
+/// Collect the information on all the plugins loaded in the functions.
+///
+/// Plugins found in the `NADI_PLUGIN_DIRS` are marked with their
+/// library path and the nadi_core ABI version they were compiled
+/// against, the rest are the internal plugins.
+pub fn plugins_info(functions: &NadiFunctions) -> Vec<PluginInfo> {
+    let external = external_plugins();
+    let internal_version = NadiExternalPlugin_Ref::VERSION_STRINGS.to_string();
+    let mut plugins: Vec<PluginInfo> = functions
+        .plugins()
+        .iter()
+        .map(|Tuple2(name, funcs)| {
+            let (path, abi_version) = match external.iter().find(|e| e.0 == name.as_str()) {
+                Some((_, p, v)) => (Some(p.clone()), v.clone()),
+                None => (None, internal_version.clone()),
+            };
+            let sorted = |f: &[abi_stable::std_types::RString]| {
+                let mut v: Vec<String> = f.iter().map(|s| s.to_string()).collect();
+                v.sort();
+                v
+            };
+            PluginInfo {
+                name: name.to_string(),
+                abi_version,
+                path,
+                env: sorted(funcs.env()),
+                node: sorted(funcs.node()),
+                network: sorted(funcs.network()),
+            }
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+fn external_plugins() -> Vec<(String, PathBuf, String)> {
+    let mut plugins = vec![];
+    let Ok(plugin_dirs) = std::env::var("NADI_PLUGIN_DIRS") else {
+        return plugins;
+    };
+    for pdir in plugin_dirs.split(':') {
+        let Ok(dir) = std::fs::read_dir(pdir) else {
+            continue;
+        };
+        for path in dir.filter_map(|p| p.ok()).map(|p| p.path()) {
+            // the errors are already shown when loading the functions
+            let mut known = EXTERNAL_PLUGINS.lock().expect("Lock poisoned");
+            let info = known.entry(path.clone()).or_insert_with(|| {
+                let lib = load_library(&path).ok()?;
+                let version = lib_header_from_path(&path)
+                    .map(|h| h.version_strings().to_string())
+                    .unwrap_or_default();
+                Some((lib.name().to_string(), version))
+            });
+            if let Some((name, version)) = info {
+                plugins.push((name.clone(), path, version.clone()));
+            }
+        }
+    }
+    plugins
+}
+
+/// Copy of the loaded functions, so the help and the terminal can
+/// use the same plugins without loading them again
+pub fn copy_functions(functions: &NadiFunctions) -> NadiFunctions {
+    let mut copy = NadiFunctions::default();
+    // the functions the short names point to are registered last, so
+    // the aliases stay the same
+    let aliased = |alias: &RHashMap<RString, RString>, name: &RString| {
+        alias.values().any(|full| full == name)
+    };
+    let prefix = |name: &str| plugin_of(name).to_string();
+    // the alias collisions were already warned about when the plugins
+    // were loaded, don't print them again on each copy
+    let _quiet = gag::Gag::stderr().ok();
+    for last in [false, true] {
+        for Tuple2(name, func) in functions.env_functions().iter() {
+            if aliased(functions.env_alias(), name) == last {
+                copy.register_env_function(&prefix(name), func.clone());
+            }
+        }
+        for Tuple2(name, func) in functions.node_functions().iter() {
+            if aliased(functions.node_alias(), name) == last {
+                copy.register_node_function(&prefix(name), func.clone());
+            }
+        }
+        for Tuple2(name, func) in functions.network_functions().iter() {
+            if aliased(functions.network_alias(), name) == last {
+                copy.register_network_function(&prefix(name), func.clone());
+            }
+        }
+    }
+    copy
+}
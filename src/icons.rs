@@ -98,3 +98,16 @@ font_icon!(trash_icon, '\u{0F1F8}');
 font_icon!(file_pdf_icon, '\u{0F1C1}');
 font_icon!(file_image_icon, '\u{0F1C5}');
 font_icon!(open_icon, '\u{0F115}');
+
+// glyphs missing from the icon font, taken from the text fonts
+macro_rules! text_icon {
+    ($name:ident, $ch:literal) => {
+        pub fn $name<'a, Message>() -> Element<'a, Message> {
+            text($ch).into()
+        }
+    };
+}
+
+text_icon!(undo_icon, '\u{21B6}');
+text_icon!(redo_icon, '\u{21B7}');
+text_icon!(debug_icon, '\u{23EF}');
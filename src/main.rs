@@ -2,7 +2,7 @@ use iced::widget::pane_grid::{self, PaneGrid};
 use iced::widget::{
     button, center, column, container, horizontal_space, pick_list, row, text, text_editor, toggler,
 };
-use iced::{Element, Fill, Length, Task, Theme};
+use iced::{Element, Fill, Length, Subscription, Task, Theme};
 use nadi::attributes::AttrView;
 use nadi::editor::{self, Editor};
 use nadi::help::{self, MdHelp};
@@ -11,11 +11,13 @@ use nadi::style;
 use nadi::svg::SvgView;
 use nadi::terminal::{self, Terminal};
 use nadi_core::attrs::HasAttributes;
+use std::sync::Arc;
 
 pub fn main() -> iced::Result {
     iced::application("NADI", MainWindow::update, MainWindow::view)
         .font(icons::FONT)
         .theme(MainWindow::theme)
+        .subscription(MainWindow::subscription)
         .run()
 }
 
@@ -28,20 +30,27 @@ struct MainWindow {
     svg: SvgView,
     terminal: Terminal,
     attrs: AttrView,
+    /// editor lines being run in the terminal, they are marked as
+    /// executed if all their tasks succeed
+    running_lines: Option<std::ops::Range<usize>>,
 }
 
 impl Default for MainWindow {
     fn default() -> Self {
         let (panes, _) = pane_grid::State::new(Pane::new());
+        let terminal = Terminal::default().embed();
+        let mut editor = Editor::default().embed();
+        editor.symbols = Arc::new(editor::complete::Symbols::new(&terminal.task_ctx));
         Self {
             light_theme: false,
             panes,
             focus: None,
             funchelp: MdHelp::default().embed(),
-            editor: Editor::default().embed(),
+            editor,
             svg: SvgView::default().embed(),
-            terminal: Terminal::default().embed(),
+            terminal,
             attrs: AttrView::default(),
+            running_lines: None,
         }
     }
 }
@@ -74,7 +83,31 @@ impl MainWindow {
                             .load_attrs(format!("Node[{}]: {}", n.index(), n.name()), n.attr_map());
                     }
                 }
-                _ => return self.terminal.update(m).map(Message::Terminal),
+                _ => {
+                    use nadi::terminal::Message as Tm;
+                    let runs = matches!(
+                        m,
+                        Tm::RunTasks(_)
+                            | Tm::TaskChain(..)
+                            | Tm::DebugRun
+                            | Tm::DebugStep
+                            | Tm::DebugContinue
+                            | Tm::DebugStop
+                    );
+                    let task = self.terminal.update(m).map(Message::Terminal);
+                    // attributes and nodes might have changed once the
+                    // tasks stop running
+                    if runs && !self.terminal.is_running() {
+                        Arc::make_mut(&mut self.editor.symbols)
+                            .load_context(&self.terminal.task_ctx);
+                        if let Some(lines) = self.running_lines.take()
+                            && !self.terminal.run_failed()
+                        {
+                            self.editor.mark_executed(lines);
+                        }
+                    }
+                    return Task::batch([task, self.sync_debug()]);
+                }
             },
             Message::SvgView(m) => return self.svg.update(m).map(Message::SvgView),
             Message::Attributes => (),
@@ -82,11 +115,13 @@ impl MainWindow {
                 return match m {
                     editor::Message::RunAllTask => {
                         let buf = self.editor.content.text();
+                        self.running_lines = Some(0..self.editor.content.line_count());
                         self.spawn_pane_maybe(Some(PaneType::Terminal));
                         Task::perform(async { buf }, terminal::Message::RunTasks)
                             .map(Message::Terminal)
                     }
                     editor::Message::RunTask => {
+                        self.running_lines = Some(self.editor.selected_lines());
                         let tasks = match self.editor.content.selection() {
                             Some(sel) => sel,
                             None => {
@@ -105,6 +140,35 @@ impl MainWindow {
                         Task::perform(async { tasks }, terminal::Message::RunTasks)
                             .map(Message::Terminal)
                     }
+                    editor::Message::RunLines(lines) => self.run_lines(lines),
+                    editor::Message::RunCell => self.run_lines(self.editor.current_cell()),
+                    editor::Message::RunCellAdvance => {
+                        let cell = self.editor.current_cell();
+                        let last = self.editor.content.line_count().saturating_sub(1);
+                        Task::batch([
+                            self.run_lines(cell.clone()),
+                            self.editor
+                                .update(editor::Message::GotoPosition(cell.end.min(last), 0))
+                                .map(Message::Editor),
+                        ])
+                    }
+                    editor::Message::Debug => {
+                        let buf = self.editor.content.text();
+                        let breakpoints = self.editor.breakpoints().clone();
+                        self.spawn_pane_maybe(Some(PaneType::Terminal));
+                        Task::perform(async { (buf, breakpoints) }, |(b, p)| {
+                            terminal::Message::Debug(b, p)
+                        })
+                        .map(Message::Terminal)
+                    }
+                    editor::Message::RunAbove => {
+                        let (line, _) = self.editor.content.cursor_position();
+                        self.run_lines(0..line)
+                    }
+                    editor::Message::RunBelow => {
+                        let (line, _) = self.editor.content.cursor_position();
+                        self.run_lines(line..self.editor.content.line_count())
+                    }
                     editor::Message::SearchHelp => {
                         if let Some(sel) = self.editor.content.selection() {
                             self.spawn_pane_maybe(Some(PaneType::FunctionHelp));
@@ -126,7 +190,26 @@ impl MainWindow {
                     _ => self.editor.update(m).map(Message::Editor),
                 };
             }
-            Message::FuncHelp(m) => self.funchelp.update(m),
+            Message::FuncHelp(m) => match m {
+                help::Message::InsertTemplate(ty, name) => {
+                    if let Some((templ, sel)) = self.funchelp.template(&ty, &name) {
+                        self.spawn_pane_maybe(Some(PaneType::TextEditor));
+                        return Task::perform(async { (templ, sel) }, |(t, s)| {
+                            editor::Message::InsertText(t, s)
+                        })
+                        .map(Message::Editor);
+                    }
+                }
+                help::Message::ReloadPlugins => {
+                    self.funchelp.reload_plugins();
+                    // keep the functions available for running tasks in sync
+                    self.terminal.task_ctx.functions =
+                        help::plugins::copy_functions(self.funchelp.functions());
+                    Arc::make_mut(&mut self.editor.symbols)
+                        .load_functions(&self.terminal.task_ctx.functions);
+                }
+                _ => return self.funchelp.update(m).map(Message::FuncHelp),
+            },
             Message::PaneTypeChanged(p, typ) => {
                 if let Some(Pane { ty, .. }) = self.panes.get_mut(p) {
                     *ty = Some(typ);
@@ -169,6 +252,19 @@ impl MainWindow {
         Task::none()
     }
 
+    fn subscription(&self) -> Subscription<Message> {
+        // keyboard shortcuts go to the focused pane
+        let focused = self
+            .focus
+            .and_then(|p| self.panes.get(p))
+            .and_then(|p| p.ty);
+        let keys = match focused {
+            Some(PaneType::FunctionHelp) => self.funchelp.subscription().map(Message::FuncHelp),
+            _ => Subscription::none(),
+        };
+        Subscription::batch([keys, self.editor.subscription().map(Message::Editor)])
+    }
+
     fn view(&self) -> Element<Message> {
         let focus = self.focus;
         let pane_grid = PaneGrid::new(&self.panes, |id, pane, is_maximized| {
@@ -239,6 +335,44 @@ impl MainWindow {
         }
     }
 
+    /// Run the lines of the editor buffer in the terminal
+    fn run_lines(&mut self, lines: std::ops::Range<usize>) -> Task<Message> {
+        if lines.is_empty() {
+            return Task::none();
+        }
+        self.running_lines = Some(lines.clone());
+        let tasks = lines
+            .filter_map(|l| self.editor.content.line(l).map(|l| l.to_string()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.spawn_pane_maybe(Some(PaneType::Terminal));
+        Task::perform(async { tasks }, terminal::Message::RunTasks).map(Message::Terminal)
+    }
+
+    /// Show the line the debugger is paused at in the editor, and the
+    /// attributes at that point. The editor is read only during the
+    /// session
+    fn sync_debug(&mut self) -> Task<Message> {
+        // the session has the lines of the tasks, they can't change
+        self.editor.set_read_only(self.terminal.is_debugging());
+        let line = self.terminal.paused_at();
+        if line == self.editor.debug_line() {
+            return Task::none();
+        }
+        self.editor.set_debug_line(line);
+        let Some(line) = line else {
+            return Task::none();
+        };
+        self.spawn_pane_maybe(Some(PaneType::AttrView));
+        self.attrs.load_context(
+            format!("Paused at line {}", line + 1),
+            &self.terminal.task_ctx,
+        );
+        self.editor
+            .update(editor::Message::GotoPosition(line, 0))
+            .map(Message::Editor)
+    }
+
     fn spawn_pane_maybe(&mut self, ty: Option<PaneType>) {
         if self.panes.iter().any(|(_, p)| p.ty == ty) {
             return;
@@ -276,6 +410,7 @@ enum PaneType {
     NetworkView,
     Terminal,
     AttrView,
+    Outline,
 }
 
 impl PaneType {
@@ -286,6 +421,7 @@ impl PaneType {
         PaneType::NetworkView,
         PaneType::Terminal,
         PaneType::AttrView,
+        PaneType::Outline,
     ];
 }
 
@@ -301,6 +437,7 @@ impl std::fmt::Display for PaneType {
                 Self::NetworkView => "Network Viewer",
                 Self::Terminal => "Terminal",
                 Self::AttrView => "Attributes",
+                Self::Outline => "Outline",
             }
         )
     }
@@ -392,6 +529,7 @@ fn pane_content<'a>(
         Some(PaneType::NetworkView) => win.terminal.view_network().map(Message::Terminal),
         Some(PaneType::Terminal) => win.terminal.view().map(Message::Terminal),
         Some(PaneType::AttrView) => win.attrs.view().map(|_| Message::Attributes),
+        Some(PaneType::Outline) => win.editor.outline_view().map(Message::Editor),
     }
 }
 
@@ -9,7 +9,9 @@ use iced::widget::{
 use iced::{Element, Fill, Font, Length, Task, Theme};
 use nadi_core::string_template::Template;
 use nadi_core::tasks::{Task as NadiTask, TaskContext};
+use std::collections::BTreeSet;
 use std::io::Read;
+use std::ops::Range;
 use std::sync::Arc;
 
 pub static NETWORK_HELP: &str = include_str!("../markdown/network.md");
@@ -29,6 +31,27 @@ pub struct Terminal {
     network_help: Vec<markdown::Item>,
     label_template: String,
     embedded: bool,
+    debug: Option<DebugSession>,
+    /// a task in the last run failed, or the tasks couldn't be parsed
+    run_failed: bool,
+}
+
+/// Tasks from the editor run one at a time, pausing at the breakpoints
+struct DebugSession {
+    /// tasks left to run in reverse order, with their editor lines
+    tasks: Vec<(Range<usize>, NadiTask)>,
+    breakpoints: BTreeSet<usize>,
+    paused: bool,
+    done: usize,
+}
+
+impl DebugSession {
+    /// Whether the next task has a breakpoint in its lines
+    fn at_breakpoint(&self) -> bool {
+        self.tasks
+            .last()
+            .is_some_and(|(lines, _)| self.breakpoints.range(lines.clone()).next().is_some())
+    }
 }
 
 impl Default for Terminal {
@@ -36,6 +59,7 @@ impl Default for Terminal {
         Self {
             light_theme: false,
             running_msg: None,
+            run_failed: false,
             history_str: vec![],
             history: combo_box::State::<String>::default(),
             command: String::new(),
@@ -48,6 +72,7 @@ impl Default for Terminal {
             network_help: markdown::parse(NETWORK_HELP).collect(),
             label_template: String::new(),
             embedded: false,
+            debug: None,
         }
     }
 }
@@ -63,6 +88,12 @@ pub enum Message {
     TemplChange(String),
     TemplSubmit,
     TaskChain(usize, Vec<NadiTask>),
+    // tasks to debug and the breakpoints, sent from the editor
+    Debug(String, BTreeSet<usize>),
+    DebugRun,
+    DebugStep,
+    DebugContinue,
+    DebugStop,
     CommandChange(String),
     History(String),
     GotoTop,
@@ -110,6 +141,103 @@ impl Terminal {
         (output, Ok(Some(results)))
     }
 
+    /// Run the task and show its output and the updated network
+    fn run_task(&mut self, task: NadiTask) {
+        let (out, res) = self.execute_task(task);
+        self.append_term(&out);
+        match res {
+            Ok(Some(s)) => self.append_term(&s),
+            Err(s) => {
+                self.run_failed = true;
+                self.append_term(&s)
+            }
+            _ => (),
+        };
+        self.network.update(
+            &self.task_ctx.network,
+            if self.label_template.is_empty() {
+                None
+            } else {
+                Template::parse_template(&self.label_template).ok()
+            },
+        );
+    }
+
+    /// Parse the tasks, the error is shown in the status
+    fn parse_tasks(&mut self, tasks: &str) -> Option<Vec<NadiTask>> {
+        let parsed = nadi_core::parser::tokenizer::get_tokens(tasks)
+            .map_err(|e| e.to_string())
+            .and_then(|tk| nadi_core::parser::tasks::parse(tk).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(t) => Some(t),
+            Err(e) => {
+                self.running_msg = None;
+                self.run_failed = true;
+                self.status = e;
+                None
+            }
+        }
+    }
+
+    /// Whether the tasks are being run, the paused debugger is not
+    /// running them
+    pub fn is_running(&self) -> bool {
+        self.running_msg.is_some() && self.paused_at().is_none()
+    }
+
+    /// Whether a debug session is going on, paused or not
+    pub fn is_debugging(&self) -> bool {
+        self.debug.is_some()
+    }
+
+    /// Whether a task in the last run failed
+    pub fn run_failed(&self) -> bool {
+        self.run_failed
+    }
+
+    /// Line in the editor of the task the debugger is paused at
+    pub fn paused_at(&self) -> Option<usize> {
+        self.debug
+            .as_ref()
+            .filter(|d| d.paused)
+            .and_then(|d| d.tasks.last())
+            .map(|(lines, _)| lines.start)
+    }
+
+    /// Run the next task of the debug session, then either pause at
+    /// the task after it or keep going until a breakpoint
+    fn debug_step(&mut self, until_break: bool) -> Task<Message> {
+        let Some((_, task)) = self.debug.as_mut().and_then(|d| d.tasks.pop()) else {
+            return self.debug_stop("finished");
+        };
+        self.run_task(task);
+        let Some(d) = &mut self.debug else {
+            return Task::none();
+        };
+        d.done += 1;
+        self.progress = d.done as f32 * 100.0 / (d.done + d.tasks.len()) as f32;
+        if d.tasks.is_empty() {
+            return self.debug_stop("finished");
+        }
+        if until_break {
+            d.paused = false;
+            self.running_msg = Some(format!("Debugging Tasks: {:.2}%", self.progress));
+            Task::perform(async {}, |_| Message::DebugRun)
+        } else {
+            d.paused = true;
+            self.running_msg = Some("Debugger Paused".to_string());
+            Task::none()
+        }
+    }
+
+    fn debug_stop(&mut self, reason: &str) -> Task<Message> {
+        if self.debug.take().is_some() {
+            self.append_term(&format!("# debugging {reason}"));
+        }
+        self.running_msg = None;
+        Task::none()
+    }
+
     fn append_term(&mut self, text: &str) {
         self.content
             .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
@@ -150,42 +278,22 @@ impl Terminal {
                     self.running_msg = None;
                     return Task::none();
                 };
-                let (out, res) = self.execute_task(task);
-                self.append_term(&out);
-                match res {
-                    Ok(Some(s)) => self.append_term(&s),
-                    Err(s) => self.append_term(&s),
-                    _ => (),
-                };
-                self.network.update(
-                    &self.task_ctx.network,
-                    if self.label_template.is_empty() {
-                        None
-                    } else {
-                        Template::parse_template(&self.label_template).ok()
-                    },
-                );
+                self.run_task(task);
                 self.progress = (done + 1) as f32 * 100.0 / (done + 1 + tasks.len()) as f32;
                 self.running_msg = Some(format!("Executing Tasks: {:.2}%", self.progress));
                 return Task::perform(async { tasks }, move |t| Message::TaskChain(done + 1, t));
             }
             Message::RunTasks(tasks) => {
+                if self.debug.is_some() {
+                    self.run_failed = true;
+                    self.status = "Stop the debugger before running other tasks".to_string();
+                    return Task::none();
+                }
                 self.append_term(&tasks);
                 self.progress = 0.0;
-                let tasks_vec = match nadi_core::parser::tokenizer::get_tokens(&tasks) {
-                    Ok(tk) => match nadi_core::parser::tasks::parse(tk) {
-                        Ok(t) => t,
-                        Err(e) => {
-                            self.running_msg = None;
-                            self.status = e.to_string();
-                            return Task::none();
-                        }
-                    },
-                    Err(e) => {
-                        self.running_msg = None;
-                        self.status = e.to_string();
-                        return Task::none();
-                    }
+                self.run_failed = false;
+                let Some(tasks_vec) = self.parse_tasks(&tasks) else {
+                    return Task::none();
                 };
                 self.append_history(tasks);
                 self.running_msg = Some(format!("Executing Tasks: {:.2}%", self.progress));
@@ -193,6 +301,57 @@ impl Terminal {
                     Message::TaskChain(0, t)
                 });
             }
+            Message::Debug(tasks, breakpoints) => {
+                if self.running_msg.is_some() {
+                    self.status = "Tasks are already running".to_string();
+                    return Task::none();
+                }
+                self.progress = 0.0;
+                self.run_failed = false;
+                let Some(tasks_vec) = self.parse_tasks(&tasks) else {
+                    return Task::none();
+                };
+                let lines = crate::editor::outline::task_lines(&tasks).unwrap_or_default();
+                if tasks_vec.is_empty() || lines.len() != tasks_vec.len() {
+                    self.status = "No tasks to debug".to_string();
+                    return Task::none();
+                }
+                self.append_term(&format!("# debugging {} tasks", tasks_vec.len()));
+                // breakpoints on the comments and blank lines stop at
+                // the next task
+                let breakpoints: BTreeSet<usize> = breakpoints
+                    .into_iter()
+                    .filter_map(|b| lines.iter().find(|l| l.end > b).map(|l| l.start.max(b)))
+                    .collect();
+                // without breakpoints, pause before the first task
+                let paused = breakpoints.is_empty();
+                self.debug = Some(DebugSession {
+                    tasks: lines.into_iter().zip(tasks_vec).rev().collect(),
+                    breakpoints,
+                    paused,
+                    done: 0,
+                });
+                if paused {
+                    self.running_msg = Some("Debugger Paused".to_string());
+                } else {
+                    self.running_msg = Some("Debugging Tasks".to_string());
+                    return Task::perform(async {}, |_| Message::DebugRun);
+                }
+            }
+            Message::DebugRun => {
+                let Some(d) = &mut self.debug else {
+                    return Task::none();
+                };
+                if d.at_breakpoint() {
+                    d.paused = true;
+                    self.running_msg = Some("Debugger Paused".to_string());
+                } else {
+                    return self.debug_step(true);
+                }
+            }
+            Message::DebugStep => return self.debug_step(false),
+            Message::DebugContinue => return self.debug_step(true),
+            Message::DebugStop => return self.debug_stop("stopped"),
             Message::ExecCommand => {
                 let task = self.command.clone();
                 self.command.clear();
@@ -207,6 +366,10 @@ impl Terminal {
                     }
                     _ => (),
                 };
+                if self.debug.is_some() {
+                    self.status = "Stop the debugger before running other tasks".to_string();
+                    return Task::none();
+                }
                 self.running_msg = Some("Executing Command".to_string());
                 return Task::perform(async { task }, Message::RunTasks);
             }
@@ -280,13 +443,14 @@ impl Terminal {
         ];
         column![
             controls.spacing(10).padding(10),
+            self.debug_view(),
             text_editor(&self.content)
                 .height(Fill)
                 .font(Font::MONOSPACE)
                 .on_action(Message::EditorAction)
                 .highlight_with::<my_hl::NadiHighlighter>(
-                    my_hl::NadiFileType::Terminal,
-                    my_hl::Highlight::to_format
+                    my_hl::NadiFileType::Terminal.into(),
+                    my_hl::Styled::to_format
                 ),
             text(&self.status),
             entry,
@@ -298,6 +462,36 @@ impl Terminal {
         .into()
     }
 
+    fn debug_view(&self) -> Element<'_, Message> {
+        let Some(d) = &self.debug else {
+            return row![].into();
+        };
+        let paused = d.paused.then_some(());
+        let msg = match self.paused_at() {
+            Some(line) => format!("Paused at line {}", line + 1),
+            None => "Running".to_string(),
+        };
+        row![
+            text(msg),
+            horizontal_space(),
+            icons::action(
+                icons::run_step_icon(),
+                "Step",
+                paused.map(|_| Message::DebugStep)
+            ),
+            icons::action(
+                icons::run_all_icon(),
+                "Continue",
+                paused.map(|_| Message::DebugContinue)
+            ),
+            icons::danger_action(icons::cancel_icon(), "Stop", Some(Message::DebugStop)),
+        ]
+        .spacing(10)
+        .padding([0, 10])
+        .align_y(iced::Alignment::Center)
+        .into()
+    }
+
     pub fn view_network(&self) -> Element<'_, Message> {
         let mut sidebar = row![
             button(center(if self.network_sidebar {